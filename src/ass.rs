@@ -0,0 +1,317 @@
+//! Guiones ASS para quemar los subtítulos con el filtro `subtitles` de FFmpeg
+//! (libass). Un archivo aparte evita meter miles de drawtext en `-filter_complex`,
+//! que supera el límite de tamaño de un argumento con un episodio largo.
+
+use std::fmt::Write;
+
+/// Estilo de la sección `[V4+ Styles]`. Los colores ya vienen en formato ASS
+/// (ver [`color`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub name: String,
+    pub font: String,
+    pub size: f64,
+    pub bold: bool,
+    pub italic: bool,
+    pub primary: String,
+    pub secondary: String,
+    pub outline_color: String,
+    pub back_color: String,
+    /// 1: contorno y sombra; 3: caja opaca del color del contorno.
+    pub border_style: u8,
+    pub outline: f64,
+    pub shadow: f64,
+}
+
+/// Cabecera del guion con la resolución del video y los estilos.
+pub fn header(width: u32, height: u32, styles: &[Style]) -> String {
+    let mut script = format!(
+        "[Script Info]\n\
+         ScriptType: v4.00+\n\
+         PlayResX: {width}\n\
+         PlayResY: {height}\n\
+         WrapStyle: 2\n\
+         ScaledBorderAndShadow: yes\n\
+         \n\
+         [V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, \
+         Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, \
+         Alignment, MarginL, MarginR, MarginV, Encoding\n"
+    );
+    for style in styles {
+        let _ = writeln!(
+            script,
+            "Style: {},{},{:.2},{},{},{},{},{},{},0,0,100,100,0,0,{},{},{},7,0,0,0,1",
+            style.name,
+            style.font.replace(',', " "),
+            style.size,
+            style.primary,
+            style.secondary,
+            style.outline_color,
+            style.back_color,
+            if style.bold { -1 } else { 0 },
+            if style.italic { -1 } else { 0 },
+            style.border_style,
+            style.outline,
+            style.shadow,
+        );
+    }
+    script.push_str("\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n");
+    script
+}
+
+/// Línea `Dialogue` de la sección `[Events]`; `text` puede llevar etiquetas `{\...}`.
+pub fn dialogue(layer: u32, start: f64, end: f64, style: &str, text: &str) -> String {
+    format!("Dialogue: {layer},{},{},{style},,0,0,0,,{text}\n", time(start), time(end))
+}
+
+/// Color de una etiqueta `\1c` (`&HBBGGRR&`) a partir de uno de [`color`]. El alfa
+/// no cambia: sigue siendo el del estilo.
+pub fn tag_color(color: &str) -> String {
+    format!("&H{}&", color.get(color.len().saturating_sub(6)..).unwrap_or_default())
+}
+
+/// `H:MM:SS.cc`, con centésimas como ASS.
+pub fn time(seconds: f64) -> String {
+    let cs = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{}:{:02}:{:02}.{:02}", cs / 360_000, cs / 6000 % 60, cs / 100 % 60, cs % 100)
+}
+
+/// Texto literal: las llaves no abren etiquetas y `\N`, `\n` o `\h` no se
+/// interpretan (se separa la barra con un carácter invisible).
+pub fn text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '{' => out.push_str(r"\{"),
+            '}' => out.push_str(r"\}"),
+            '\\' => out.push_str("\\\u{2060}"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Color de FFmpeg (`white`, `#FF8800`, `0xFF8800CC`, `black@0.6`) en formato ASS
+/// (`&HAABBGGRR`, con el alfa invertido). `opacity` multiplica la opacidad.
+pub fn color(value: &str, opacity: f64) -> Option<String> {
+    let (name, alpha) = match value.split_once('@') {
+        Some((name, alpha)) => (name, Some(alpha)),
+        None => (value, None),
+    };
+    let name = name.trim();
+    let hex = name
+        .strip_prefix('#')
+        .or_else(|| name.strip_prefix("0x"))
+        .or_else(|| name.strip_prefix("0X"));
+    let (rgb, mut a) = match hex {
+        Some(hex) => parse_hex(hex)?,
+        None => match COLOR_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            Some(&(_, rgb)) => (rgb, 1.0),
+            None => parse_hex(name)?,
+        },
+    };
+    if let Some(alpha) = alpha {
+        a = match alpha.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16).ok()? as f64 / 255.0,
+            None => alpha.parse::<f64>().ok().filter(|a| (0.0..=1.0).contains(a))?,
+        };
+    }
+    let a = 255 - (a * opacity.clamp(0.0, 1.0) * 255.0).round() as u32;
+    let (r, g, b) = (rgb >> 16, (rgb >> 8) & 0xFF, rgb & 0xFF);
+    Some(format!("&H{a:02X}{b:02X}{g:02X}{r:02X}"))
+}
+
+/// `RRGGBB` o `RRGGBBAA`.
+fn parse_hex(hex: &str) -> Option<(u32, f64)> {
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some((value, 1.0)),
+        8 => Some((value >> 8, (value & 0xFF) as f64 / 255.0)),
+        _ => None,
+    }
+}
+
+/// Evalúa una expresión de posición de FFmpeg (`(w-text_w)/2`, `h-th-90`) con
+/// las variables dadas. Admite `+ - * /`, paréntesis y las funciones más comunes;
+/// `None` si usa algo más (por ejemplo, el tiempo `t`).
+pub fn eval(expr: &str, vars: &[(&str, f64)]) -> Option<f64> {
+    let mut parser = Parser { input: expr.as_bytes(), pos: 0, vars };
+    let value = parser.expr()?;
+    parser.skip_spaces();
+    (parser.pos == parser.input.len() && value.is_finite()).then_some(value)
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    vars: &'a [(&'a str, f64)],
+}
+
+impl Parser<'_> {
+    fn skip_spaces(&mut self) {
+        while self.input.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_spaces();
+        let found = self.input.get(self.pos) == Some(&c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat(b'+') {
+                value += self.term()?;
+            } else if self.eat(b'-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat(b'*') {
+                value *= self.unary()?;
+            } else if self.eat(b'/') {
+                value /= self.unary()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        if self.eat(b'-') {
+            return Some(-self.unary()?);
+        }
+        if self.eat(b'+') {
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Option<f64> {
+        if self.eat(b'(') {
+            let value = self.expr()?;
+            return self.eat(b')').then_some(value);
+        }
+        self.skip_spaces();
+        let start = self.pos;
+        let rest = &self.input[start..];
+        if rest.first().is_some_and(|c| c.is_ascii_digit() || *c == b'.') {
+            let len = rest.iter().take_while(|c| c.is_ascii_digit() || **c == b'.').count();
+            self.pos += len;
+            return std::str::from_utf8(&rest[..len]).ok()?.parse().ok();
+        }
+        let len = rest.iter().take_while(|c| c.is_ascii_alphanumeric() || **c == b'_').count();
+        let name = std::str::from_utf8(&rest[..len]).ok()?;
+        self.pos += len;
+        if !self.eat(b'(') {
+            return self.vars.iter().find(|(var, _)| *var == name).map(|&(_, value)| value);
+        }
+        let mut args = vec![self.expr()?];
+        while self.eat(b',') {
+            args.push(self.expr()?);
+        }
+        if !self.eat(b')') {
+            return None;
+        }
+        match (name, args.as_slice()) {
+            ("abs", [x]) => Some(x.abs()),
+            ("floor", [x]) => Some(x.floor()),
+            ("ceil", [x]) => Some(x.ceil()),
+            ("trunc", [x]) => Some(x.trunc()),
+            ("round", [x]) => Some(x.round()),
+            ("sqrt", [x]) => Some(x.sqrt()),
+            ("min", [a, b]) => Some(a.min(*b)),
+            ("max", [a, b]) => Some(a.max(*b)),
+            ("pow", [a, b]) => Some(a.powf(*b)),
+            ("mod", [a, b]) => Some(a % b),
+            ("clip", [x, min, max]) => Some(x.clamp(*min, *max)),
+            _ => None,
+        }
+    }
+}
+
+/// Nombres de color que acepta FFmpeg (los de CSS).
+const COLOR_NAMES: &[(&str, u32)] = &[
+    ("AliceBlue", 0xF0F8FF), ("AntiqueWhite", 0xFAEBD7), ("Aqua", 0x00FFFF), ("Aquamarine", 0x7FFFD4),
+    ("Azure", 0xF0FFFF), ("Beige", 0xF5F5DC), ("Bisque", 0xFFE4C4), ("Black", 0x000000),
+    ("BlanchedAlmond", 0xFFEBCD), ("Blue", 0x0000FF), ("BlueViolet", 0x8A2BE2), ("Brown", 0xA52A2A),
+    ("BurlyWood", 0xDEB887), ("CadetBlue", 0x5F9EA0), ("Chartreuse", 0x7FFF00), ("Chocolate", 0xD2691E),
+    ("Coral", 0xFF7F50), ("CornflowerBlue", 0x6495ED), ("Cornsilk", 0xFFF8DC), ("Crimson", 0xDC143C),
+    ("Cyan", 0x00FFFF), ("DarkBlue", 0x00008B), ("DarkCyan", 0x008B8B), ("DarkGoldenRod", 0xB8860B),
+    ("DarkGray", 0xA9A9A9), ("DarkGreen", 0x006400), ("DarkKhaki", 0xBDB76B), ("DarkMagenta", 0x8B008B),
+    ("DarkOliveGreen", 0x556B2F), ("Darkorange", 0xFF8C00), ("DarkOrchid", 0x9932CC), ("DarkRed", 0x8B0000),
+    ("DarkSalmon", 0xE9967A), ("DarkSeaGreen", 0x8FBC8F), ("DarkSlateBlue", 0x483D8B), ("DarkSlateGray", 0x2F4F4F),
+    ("DarkTurquoise", 0x00CED1), ("DarkViolet", 0x9400D3), ("DeepPink", 0xFF1493), ("DeepSkyBlue", 0x00BFFF),
+    ("DimGray", 0x696969), ("DodgerBlue", 0x1E90FF), ("FireBrick", 0xB22222), ("FloralWhite", 0xFFFAF0),
+    ("ForestGreen", 0x228B22), ("Fuchsia", 0xFF00FF), ("Gainsboro", 0xDCDCDC), ("GhostWhite", 0xF8F8FF),
+    ("Gold", 0xFFD700), ("GoldenRod", 0xDAA520), ("Gray", 0x808080), ("Green", 0x008000),
+    ("GreenYellow", 0xADFF2F), ("HoneyDew", 0xF0FFF0), ("HotPink", 0xFF69B4), ("IndianRed", 0xCD5C5C),
+    ("Indigo", 0x4B0082), ("Ivory", 0xFFFFF0), ("Khaki", 0xF0E68C), ("Lavender", 0xE6E6FA),
+    ("LavenderBlush", 0xFFF0F5), ("LawnGreen", 0x7CFC00), ("LemonChiffon", 0xFFFACD), ("LightBlue", 0xADD8E6),
+    ("LightCoral", 0xF08080), ("LightCyan", 0xE0FFFF), ("LightGoldenRodYellow", 0xFAFAD2), ("LightGreen", 0x90EE90),
+    ("LightGrey", 0xD3D3D3), ("LightPink", 0xFFB6C1), ("LightSalmon", 0xFFA07A), ("LightSeaGreen", 0x20B2AA),
+    ("LightSkyBlue", 0x87CEFA), ("LightSlateGray", 0x778899), ("LightSteelBlue", 0xB0C4DE), ("LightYellow", 0xFFFFE0),
+    ("Lime", 0x00FF00), ("LimeGreen", 0x32CD32), ("Linen", 0xFAF0E6), ("Magenta", 0xFF00FF),
+    ("Maroon", 0x800000), ("MediumAquaMarine", 0x66CDAA), ("MediumBlue", 0x0000CD), ("MediumOrchid", 0xBA55D3),
+    ("MediumPurple", 0x9370D8), ("MediumSeaGreen", 0x3CB371), ("MediumSlateBlue", 0x7B68EE), ("MediumSpringGreen", 0x00FA9A),
+    ("MediumTurquoise", 0x48D1CC), ("MediumVioletRed", 0xC71585), ("MidnightBlue", 0x191970), ("MintCream", 0xF5FFFA),
+    ("MistyRose", 0xFFE4E1), ("Moccasin", 0xFFE4B5), ("NavajoWhite", 0xFFDEAD), ("Navy", 0x000080),
+    ("OldLace", 0xFDF5E6), ("Olive", 0x808000), ("OliveDrab", 0x6B8E23), ("Orange", 0xFFA500),
+    ("OrangeRed", 0xFF4500), ("Orchid", 0xDA70D6), ("PaleGoldenRod", 0xEEE8AA), ("PaleGreen", 0x98FB98),
+    ("PaleTurquoise", 0xAFEEEE), ("PaleVioletRed", 0xD87093), ("PapayaWhip", 0xFFEFD5), ("PeachPuff", 0xFFDAB9),
+    ("Peru", 0xCD853F), ("Pink", 0xFFC0CB), ("Plum", 0xDDA0DD), ("PowderBlue", 0xB0E0E6),
+    ("Purple", 0x800080), ("Red", 0xFF0000), ("RosyBrown", 0xBC8F8F), ("RoyalBlue", 0x4169E1),
+    ("SaddleBrown", 0x8B4513), ("Salmon", 0xFA8072), ("SandyBrown", 0xF4A460), ("SeaGreen", 0x2E8B57),
+    ("SeaShell", 0xFFF5EE), ("Sienna", 0xA0522D), ("Silver", 0xC0C0C0), ("SkyBlue", 0x87CEEB),
+    ("SlateBlue", 0x6A5ACD), ("SlateGray", 0x708090), ("Snow", 0xFFFAFA), ("SpringGreen", 0x00FF7F),
+    ("SteelBlue", 0x4682B4), ("Tan", 0xD2B48C), ("Teal", 0x008080), ("Thistle", 0xD8BFD8),
+    ("Tomato", 0xFF6347), ("Turquoise", 0x40E0D0), ("Violet", 0xEE82EE), ("Wheat", 0xF5DEB3),
+    ("White", 0xFFFFFF), ("WhiteSmoke", 0xF5F5F5), ("Yellow", 0xFFFF00), ("YellowGreen", 0x9ACD32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color() {
+        assert_eq!(color("white", 1.0).unwrap(), "&H00FFFFFF");
+        assert_eq!(color("#FF8800", 1.0).unwrap(), "&H000088FF");
+        assert_eq!(color("0xFF880080", 1.0).unwrap(), "&H7F0088FF");
+        assert_eq!(color("black@0.6", 1.0).unwrap(), "&H66000000");
+        assert_eq!(color("DarkOrange", 0.5).unwrap(), "&H7F008CFF");
+        assert_eq!(color("notacolor", 1.0), None);
+        assert_eq!(color("red@2", 1.0), None);
+        assert_eq!(tag_color("&H7F0088FF"), "&H0088FF&");
+    }
+
+    #[test]
+    fn test_time_and_text() {
+        assert_eq!(time(3725.456), "1:02:05.46");
+        assert_eq!(time(-1.0), "0:00:00.00");
+        assert_eq!(text(r"{\b1} C:\Nuevo"), "\\{\\\u{2060}b1\\} C:\\\u{2060}Nuevo");
+    }
+
+    #[test]
+    fn test_eval() {
+        let vars = [("w", 1920.0), ("h", 1080.0), ("text_w", 500.0), ("text_h", 60.0)];
+        assert_eq!(eval("(w-text_w)/2", &vars), Some(710.0));
+        assert_eq!(eval("h - text_h - 90", &vars), Some(930.0));
+        assert_eq!(eval("-10+max(20, w/100)*2", &vars), Some(30.0));
+        assert_eq!(eval("w-t*100", &vars), None);
+        assert_eq!(eval("(w", &vars), None);
+    }
+}
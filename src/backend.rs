@@ -0,0 +1,78 @@
+use crate::error::{AudiowaveError, Result};
+use log::debug;
+use std::{future::Future, process::Stdio};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::mpsc,
+};
+
+/// Cómo terminó el proceso multimedia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitStatus {
+    pub success: bool,
+    /// Código de salida, o `None` si el proceso terminó por una señal.
+    pub code: Option<i32>,
+}
+
+/// Ejecuta FFmpeg (o algo que se comporte como él). Permite sustituir el proceso real
+/// por una implementación falsa en los tests.
+pub trait MediaBackend: Send + Sync + 'static {
+    /// Lanza el proceso con `args`, envía cada línea de stderr por `lines` y espera
+    /// a que termine.
+    fn run(
+        &self,
+        args: Vec<String>,
+        lines: mpsc::UnboundedSender<String>,
+    ) -> impl Future<Output = Result<ExitStatus>> + Send;
+}
+
+/// Backend que ejecuta el binario de FFmpeg.
+#[derive(Debug, Clone)]
+pub struct FfmpegBackend {
+    program: String,
+}
+
+impl Default for FfmpegBackend {
+    fn default() -> Self {
+        Self::new("ffmpeg")
+    }
+}
+
+impl FfmpegBackend {
+    /// `program` es la ruta del ejecutable (o su nombre si está en el PATH).
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+        }
+    }
+}
+
+impl MediaBackend for FfmpegBackend {
+    async fn run(&self, args: Vec<String>, lines: mpsc::UnboundedSender<String>) -> Result<ExitStatus> {
+        let mut ffmpeg_cmd = Command::new(&self.program);
+        // Si se suelta el renderizado (por ejemplo, al cancelarlo), FFmpeg no sigue solo
+        ffmpeg_cmd.args(args).stderr(Stdio::piped()).kill_on_drop(true);
+
+        // Imprimir el comando generado como debug antes de spawn
+        debug!("🚀 Ejecutando comando: {:?}", ffmpeg_cmd);
+
+        let mut child = ffmpeg_cmd.spawn().map_err(AudiowaveError::FfmpegSpawn)?;
+
+        let stderr = child.stderr.take().ok_or_else(|| {
+            AudiowaveError::FfmpegSpawn(std::io::Error::other("No se pudo capturar stderr de FFmpeg"))
+        })?;
+        let mut reader = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+
+        let status = child.wait().await.map_err(AudiowaveError::FfmpegSpawn)?;
+        Ok(ExitStatus {
+            success: status.success(),
+            code: status.code(),
+        })
+    }
+}
@@ -0,0 +1,629 @@
+//! Subtítulos quemados en el video a partir de transcripciones SRT, WebVTT o JSON
+//! con tiempos por palabra (formato de whisper).
+
+use crate::ass;
+use crate::error::{AudiowaveError, Result};
+use crate::escape;
+use crate::font::{self, FaceInfo, FontSpec};
+use crate::layout::{self, Metrics};
+use crate::models::animation::Edge;
+use crate::models::text::{TextAlign, TextSettings};
+use regex::Regex;
+use serde::Deserialize;
+use std::{path::Path, sync::LazyLock};
+
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").expect("Regex válida"));
+
+/// Color por defecto de la palabra que se está diciendo.
+pub const DEFAULT_HIGHLIGHT: &str = "yellow";
+
+/// Estilo de los subtítulos: el de una capa de texto más el color de resaltado.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CaptionSettings {
+    #[serde(flatten)]
+    pub style: TextSettings,
+    /// Color de la palabra que se está diciendo cuando la transcripción tiene
+    /// tiempos por palabra (modo karaoke).
+    #[serde(default)]
+    pub highlight: Option<String>,
+}
+
+/// Un subtítulo: texto visible entre `start` y `end` (segundos del audio).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    /// Tiempos de cada palabra de `text`, en orden; vacío en SRT y WebVTT.
+    pub words: Vec<Word>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Word {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Lee un archivo `.srt`, `.vtt` o `.json` (transcripción de whisper).
+pub async fn load(path: &Path) -> Result<Vec<Cue>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| AudiowaveError::Captions(format!("{}: {e}", path.display())))?;
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let cues = if is_json { parse_json(&content) } else { parse(&content) };
+    cues.map_err(|msg| AudiowaveError::Captions(format!("{}: {msg}", path.display())))
+}
+
+#[derive(Deserialize)]
+struct Transcript {
+    #[serde(default)]
+    segments: Vec<Segment>,
+    /// Algunas herramientas solo dan la lista de palabras.
+    #[serde(default)]
+    words: Vec<TranscriptWord>,
+}
+
+#[derive(Deserialize)]
+struct Segment {
+    start: f64,
+    end: f64,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    words: Vec<TranscriptWord>,
+}
+
+#[derive(Deserialize)]
+struct TranscriptWord {
+    #[serde(alias = "text")]
+    word: String,
+    start: Option<f64>,
+    end: Option<f64>,
+}
+
+/// Interpreta la salida JSON de whisper (`segments[].words[]` con `word`, `start`
+/// y `end`). A las palabras sin tiempos se les asigna el hueco que dejan sus
+/// vecinas.
+pub fn parse_json(content: &str) -> std::result::Result<Vec<Cue>, String> {
+    let transcript: Transcript = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let mut segments = transcript.segments;
+    if segments.is_empty() && !transcript.words.is_empty() {
+        let start = transcript.words.iter().find_map(|w| w.start).unwrap_or(0.0);
+        let end = transcript.words.iter().rev().find_map(|w| w.end).unwrap_or(start);
+        segments.push(Segment { start, end, text: String::new(), words: transcript.words });
+    }
+
+    let mut cues = Vec::new();
+    for segment in segments {
+        let mut words: Vec<Word> = Vec::new();
+        for (i, word) in segment.words.iter().enumerate() {
+            let text = word.word.trim().to_string();
+            if text.is_empty() {
+                continue;
+            }
+            let start = word
+                .start
+                .or(words.last().map(|w| w.end))
+                .unwrap_or(segment.start);
+            let end = word
+                .end
+                .or_else(|| segment.words[i + 1..].iter().find_map(|w| w.start))
+                .unwrap_or(segment.end)
+                .max(start);
+            words.push(Word { start, end, text });
+        }
+        let text = if words.is_empty() {
+            segment.text.split_whitespace().collect::<Vec<_>>().join(" ")
+        } else {
+            words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ")
+        };
+        if !text.is_empty() && segment.end > segment.start {
+            cues.push(Cue { start: segment.start, end: segment.end, text, words });
+        }
+    }
+    Ok(cues)
+}
+
+/// Interpreta SRT y WebVTT: los dos son bloques separados por líneas en blanco con
+/// una línea `inicio --> fin`. Cabeceras, notas y estilos se ignoran, igual que las
+/// etiquetas (`<b>`, `<v Ana>`) y los ajustes de posición de VTT.
+pub fn parse(content: &str) -> std::result::Result<Vec<Cue>, String> {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    let mut line_number = 1;
+    for block in content.split("\n\n") {
+        let lines: Vec<&str> = block.lines().collect();
+        let timing = lines.iter().position(|line| line.contains("-->"));
+        if let Some(i) = timing {
+            let (start, end) = lines[i]
+                .split_once("-->")
+                .expect("La línea contiene -->");
+            let end = end.split_whitespace().next().unwrap_or("");
+            let invalid = |value: &str| format!("línea {}: marca de tiempo no válida '{value}'", line_number + i);
+            let start = parse_time(start.trim()).ok_or_else(|| invalid(start.trim()))?;
+            let end = parse_time(end).ok_or_else(|| invalid(end))?;
+            let text = lines[i + 1..].join(" ");
+            let text = TAG
+                .replace_all(&text, "")
+                .replace("&nbsp;", " ")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&amp;", "&");
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !text.is_empty() && end > start {
+                cues.push(Cue { start, end, text, words: Vec::new() });
+            }
+        }
+        line_number += block.lines().count() + 1;
+    }
+    Ok(cues)
+}
+
+/// `HH:MM:SS,mmm`, `HH:MM:SS.mmm` o `MM:SS.mmm`.
+fn parse_time(value: &str) -> Option<f64> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let seconds: f64 = parts.last()?.replace(',', ".").parse().ok()?;
+    let minutes = parts[..parts.len() - 1]
+        .iter()
+        .try_fold(0u64, |acc, part| part.parse::<u64>().ok().map(|n| acc * 60 + n))?;
+    Some(minutes as f64 * 60.0 + seconds)
+}
+
+/// Desplaza los subtítulos `offset` segundos hacia atrás (para renderizar desde
+/// `--start`) y descarta los que quedan antes del cero.
+pub fn shift(cues: &[Cue], offset: f64) -> Vec<Cue> {
+    cues.iter()
+        .filter(|cue| cue.end > offset)
+        .map(|cue| Cue {
+            start: (cue.start - offset).max(0.0),
+            end: cue.end - offset,
+            text: cue.text.clone(),
+            words: cue
+                .words
+                .iter()
+                .map(|word| Word {
+                    start: word.start - offset,
+                    end: word.end - offset,
+                    text: word.text.clone(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Guion ASS con los subtítulos para un video de `width`x`height`. Los subtítulos
+/// largos se parten en trozos de `max_lines` líneas. Con tiempos por palabra, cada
+/// trozo dura lo que sus palabras; sin ellos, el tiempo se reparte según la
+/// cantidad de texto.
+pub fn to_ass(cues: &[Cue], settings: &CaptionSettings, width: u32, height: u32) -> String {
+    let style = &settings.style;
+    let metrics = style.metrics();
+    let max_width = style.max_width.unwrap_or(u32::MAX) as f64;
+    let max_lines = style.max_lines.unwrap_or(u32::MAX).max(1) as usize;
+    let text_style = settings.ass_style(&metrics);
+    let accent = settings.highlight.as_deref().unwrap_or(DEFAULT_HIGHLIGHT);
+    let colors = KaraokeColors {
+        accent: ass::tag_color(&ass::color(accent, 1.0).unwrap_or_else(|| text_style.primary.clone())),
+        text: ass::tag_color(&text_style.primary),
+    };
+    let mut script = ass::header(width, height, std::slice::from_ref(&text_style));
+    for cue in cues {
+        let text = escape::drawtext_value(&cue.text);
+        let lines = layout::wrap(&text, &metrics, style.size, max_width);
+        let chunks: Vec<String> = lines.chunks(max_lines).map(|chunk| chunk.join(" ")).collect();
+        let counts: Vec<usize> = chunks.iter().map(|c| c.split_whitespace().count()).collect();
+        // Si alguna palabra trae espacios no se puede emparejar con el texto partido
+        let karaoke = !cue.words.is_empty() && counts.iter().sum::<usize>() == cue.words.len();
+
+        let total: usize = chunks.iter().map(|c| layout::visible(c).chars().count()).sum();
+        let mut start = cue.start;
+        let mut first_word = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let words = &cue.words.get(first_word..first_word + counts[i]).unwrap_or_default();
+            let end = if karaoke {
+                cue.words.get(first_word + counts[i]).map_or(cue.end, |next| next.start.max(start))
+            } else {
+                let share = layout::visible(chunk).chars().count() as f64 / total.max(1) as f64;
+                start + (cue.end - cue.start) * share
+            };
+            let (tags, lines) = settings.event(chunk, start, end, &metrics, width, height);
+            let shadow = style
+                .shadow
+                .as_ref()
+                .map(|s| format!(r"\xshad{}\yshad{}", s.x, s.y))
+                .unwrap_or_default();
+            // Con tiempos por palabra, el mismo evento cambia el color de cada una
+            // mientras se dice: otra capa encima sumaría opacidad con `alpha` < 1
+            let text = if karaoke {
+                karaoke_text(&lines, words, start, &colors)
+            } else {
+                lines.iter().map(|line| ass::text(line)).collect::<Vec<_>>().join(r"\N")
+            };
+            script.push_str(&ass::dialogue(0, start, end, STYLE, &format!("{{{tags}{shadow}}}{text}")));
+            first_word += counts[i];
+            start = end;
+        }
+    }
+    script
+}
+
+/// Filtro `subtitles` que quema el guion de `path`. Si la fuente es un archivo,
+/// su carpeta se le pasa a libass en `fontsdir`; `alpha` conserva la transparencia.
+pub fn subtitles_filter(path: &Path, settings: &CaptionSettings, alpha: bool) -> String {
+    let mut filter = format!("subtitles=filename={}", escape::filter_arg(&path.to_string_lossy()));
+    if let Some(FontSpec::Path(font)) = font::chain(&settings.style.font).into_iter().next()
+        && let Some(dir) = font.parent()
+    {
+        filter += &format!(":fontsdir={}", escape::filter_arg(&dir.to_string_lossy()));
+    }
+    if alpha {
+        filter += ":alpha=1";
+    }
+    filter
+}
+
+/// Colores de las etiquetas `\1c` del karaoke.
+struct KaraokeColors {
+    accent: String,
+    text: String,
+}
+
+/// Texto en el que cada palabra pasa al color de resalte entre su `start` y su
+/// `end`, contados en milisegundos desde `start` con `\t`.
+fn karaoke_text(lines: &[String], words: &[Word], start: f64, colors: &KaraokeColors) -> String {
+    let ms = |seconds: f64| ((seconds - start) * 1000.0).round().max(0.0) as u64;
+    let KaraokeColors { accent, text: color } = colors;
+    let mut words = words.iter();
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            text.push_str(r"\N");
+        }
+        for (j, token) in line.split_whitespace().enumerate() {
+            let Some(word) = words.next() else {
+                break;
+            };
+            if j > 0 {
+                text.push(' ');
+            }
+            // Cada palabra vuelve a empezar en el color del texto; `\t(0,0,...)`
+            // duraría todo el evento, así que lo que empieza en 0 se pinta directamente
+            let tags = match (ms(word.start), ms(word.end)) {
+                (from, to) if from >= to => format!(r"\1c{color}"),
+                (0, to) => format!(r"\1c{accent}\t({to},{to},\1c{color})"),
+                (from, to) => format!(r"\1c{color}\t({from},{from},\1c{accent})\t({to},{to},\1c{color})"),
+            };
+            text += &format!("{{{tags}}}{}", ass::text(token));
+        }
+    }
+    text
+}
+
+/// Nombre del estilo del guion.
+const STYLE: &str = "Captions";
+
+impl CaptionSettings {
+    /// Problemas del estilo, incluidos los que libass no puede reproducir.
+    pub fn validate(&self) -> Vec<String> {
+        let style = &self.style;
+        let mut problems = style.validate("captions");
+        let colors = [
+            ("color", Some(&style.color)),
+            ("highlight", self.highlight.as_ref()),
+            ("shadow.color", style.shadow.as_ref().map(|s| &s.color)),
+            ("border.color", style.border.as_ref().map(|b| &b.color)),
+            ("box.color", style.text_box.as_ref().map(|b| &b.color)),
+        ];
+        for (field, color) in colors {
+            if let Some(color) = color
+                && ass::color(color, 1.0).is_none()
+            {
+                problems.push(format!("captions: {field} ('{color}') no es un color válido"));
+            }
+        }
+        for (field, expr) in [("x", &style.x), ("y", &style.y)] {
+            if ass::eval(expr, &position_vars(1920.0, 1080.0, 100.0, 100.0)).is_none() {
+                problems.push(format!(
+                    "captions: {field} ('{expr}') solo admite números, w, h, text_w, text_h y operaciones sencillas"
+                ));
+            }
+        }
+        if style.animation.as_ref().is_some_and(|a| a.typewriter.is_some()) {
+            problems.push("captions: animation.typewriter no se admite en los subtítulos".to_string());
+        }
+        problems
+    }
+
+    /// Estilo ASS equivalente: la fuente se pide por familia y el tamaño se pasa
+    /// al alto de celda que usa libass. La caja sustituye al contorno.
+    fn ass_style(&self, metrics: &Metrics) -> ass::Style {
+        let style = &self.style;
+        let alpha = style.alpha.unwrap_or(1.0);
+        let face = FaceInfo::of(&style.font);
+        let color = |value: &str| ass::color(value, alpha).unwrap_or_else(|| OPAQUE_BLACK.to_string());
+        let (border_style, outline, outline_color) = match (&style.text_box, &style.border) {
+            (Some(text_box), _) => (3, text_box.padding as f64, color(&text_box.color)),
+            (None, Some(border)) => (1, border.width as f64, color(&border.color)),
+            (None, None) => (1, 0.0, OPAQUE_BLACK.to_string()),
+        };
+        ass::Style {
+            name: STYLE.to_string(),
+            font: face.family,
+            size: metrics.cell_height(style.size),
+            bold: face.bold,
+            italic: face.italic,
+            primary: color(&style.color),
+            secondary: color(&style.color),
+            outline_color,
+            back_color: style.shadow.as_ref().map_or(OPAQUE_BLACK.to_string(), |s| color(&s.color)),
+            border_style,
+            outline,
+            shadow: 0.0,
+        }
+    }
+
+    /// Etiquetas de posición y fundido (sin llaves) y líneas visibles de un trozo:
+    /// se mide igual que una capa de texto y se coloca con `\pos` evaluando `x` e `y`.
+    fn event(&self, chunk: &str, start: f64, end: f64, metrics: &Metrics, width: u32, height: u32) -> (String, Vec<String>) {
+        let style = &self.style;
+        let min_size = style.min_size.unwrap_or(style.size / 2);
+        let max_width = style.max_width.unwrap_or(u32::MAX);
+        let block = layout::fit(chunk, metrics, style.size, min_size, max_width, style.max_lines);
+        let line_height = block.line_height + style.line_spacing.unwrap_or(0) as f64;
+        let (block_w, block_h) = (block.width(), line_height * block.lines.len() as f64);
+
+        let (w, h) = (width as f64, height as f64);
+        let vars = position_vars(w, h, block_w, block_h);
+        let x = ass::eval(&style.x, &vars).unwrap_or((w - block_w) / 2.0);
+        let y = ass::eval(&style.y, &vars).unwrap_or(h - block_h - h / 12.0);
+        // `\an7`, `\an8` y `\an9` anclan el bloque por arriba a la izquierda, el
+        // centro o la derecha, y libass alinea así cada línea
+        let (an, anchor) = match style.align {
+            TextAlign::Left => (7, 0.0),
+            TextAlign::Center => (8, block_w / 2.0),
+            TextAlign::Right => (9, block_w),
+        };
+        let mut tags = format!(r"\an{an}");
+        let position = (x + anchor, y);
+        match style.animation.as_ref().and_then(|a| a.slide.as_ref()) {
+            Some(slide) => {
+                let from = match slide.from {
+                    Edge::Left => (-block_w + anchor, y),
+                    Edge::Right => (w + anchor, y),
+                    Edge::Top => (x + anchor, -block_h),
+                    Edge::Bottom => (x + anchor, h),
+                };
+                let ms = (slide.duration * 1000.0).round();
+                tags += &format!(
+                    r"\move({:.0},{:.0},{:.0},{:.0},0,{ms})",
+                    from.0, from.1, position.0, position.1
+                );
+            }
+            None => tags += &format!(r"\pos({:.0},{:.0})", position.0, position.1),
+        }
+        if block.size != style.size {
+            tags += &format!(r"\fs{:.2}", metrics.cell_height(block.size));
+        }
+        if let Some(animation) = &style.animation
+            && (animation.fade_in.is_some() || animation.fade_out.is_some())
+        {
+            let ms = |seconds: Option<f64>| (seconds.unwrap_or(0.0).min(end - start) * 1000.0).round();
+            tags += &format!(r"\fad({},{})", ms(animation.fade_in), ms(animation.fade_out));
+        }
+        (tags, block.lines.iter().map(|line| layout::visible(line)).collect())
+    }
+}
+
+/// Negro opaco: color de respaldo y de los contornos sin color.
+const OPAQUE_BLACK: &str = "&H00000000";
+
+/// Variables de las expresiones de posición, como en drawtext.
+fn position_vars(w: f64, h: f64, text_w: f64, text_h: f64) -> [(&'static str, f64); 10] {
+    [
+        ("w", w),
+        ("h", h),
+        ("W", w),
+        ("H", h),
+        ("main_w", w),
+        ("main_h", h),
+        ("text_w", text_w),
+        ("tw", text_w),
+        ("text_h", text_h),
+        ("th", text_h),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRT: &str = "1\r\n00:00:01,000 --> 00:00:03,500\r\nHola, <i>bienvenidos</i>\r\nal podcast\r\n\r\n2\r\n00:00:04,000 --> 00:00:05,000\r\nR&amp;D\r\n";
+
+    const VTT: &str = "WEBVTT\n\nNOTE esto se ignora\n\nintro\n00:01.000 --> 00:03.500 line:90%\n<v Ana>Hola</v>\n\n01:00:00.250 --> 01:00:01.000\nFin\n";
+
+    #[test]
+    fn test_parse_srt() {
+        let cues = parse(SRT).unwrap();
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, "Hola, bienvenidos al podcast");
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 3.5));
+        assert_eq!(cues[1].text, "R&D");
+    }
+
+    #[test]
+    fn test_parse_vtt() {
+        let cues = parse(VTT).unwrap();
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].text, "Hola");
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 3.5));
+        assert_eq!(cues[1].start, 3600.25);
+    }
+
+    #[test]
+    fn test_parse_invalid_time() {
+        let err = parse("1\n00:00:xx,000 --> 00:00:02,000\nHola\n").unwrap_err();
+        assert!(err.contains("línea 2"));
+    }
+
+    #[test]
+    fn test_shift() {
+        let cues = parse(SRT).unwrap();
+        let shifted = shift(&cues, 3.0);
+        assert_eq!(shifted.len(), 2);
+        assert_eq!((shifted[0].start, shifted[0].end), (0.0, 0.5));
+        assert_eq!(shift(&cues, 10.0), Vec::new());
+    }
+
+    const WHISPER: &str = r#"{
+        "text": " Hola a todos",
+        "segments": [
+            {"id": 0, "start": 0.0, "end": 2.0, "text": " Hola a todos", "words": [
+                {"word": " Hola", "start": 0.0, "end": 0.5, "probability": 0.9},
+                {"word": " a", "start": 0.6, "end": 0.7},
+                {"word": " todos", "start": 0.8, "end": 1.6}
+            ]},
+            {"start": 2.0, "end": 3.0, "text": " 20 euros", "words": [
+                {"word": "20"},
+                {"word": " euros", "start": 2.5, "end": 3.0}
+            ]},
+            {"start": 3.0, "end": 4.0, "text": " Sin palabras"}
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_whisper_json() {
+        let cues = parse_json(WHISPER).unwrap();
+        assert_eq!(cues.len(), 3);
+        assert_eq!(cues[0].text, "Hola a todos");
+        assert_eq!(cues[0].words[2], Word { start: 0.8, end: 1.6, text: "todos".to_string() });
+        // Sin tiempos, "20" ocupa desde el inicio del segmento hasta la siguiente palabra
+        assert_eq!((cues[1].words[0].start, cues[1].words[0].end), (2.0, 2.5));
+        assert_eq!(cues[2].text, "Sin palabras");
+        assert!(cues[2].words.is_empty());
+
+        let only_words = r#"{"words": [{"text": "Hola", "start": 1.0, "end": 1.5}]}"#;
+        let cues = parse_json(only_words).unwrap();
+        assert_eq!((cues[0].start, cues[0].end), (1.0, 1.5));
+        assert!(parse_json("{").is_err());
+    }
+
+    #[test]
+    fn test_karaoke_highlights_each_word() {
+        let settings = CaptionSettings {
+            style: TextSettings {
+                font: "sans-serif".to_string(),
+                size: 50,
+                color: "white".to_string(),
+                x: "100".to_string(),
+                y: "h-100".to_string(),
+                max_width: Some(240),
+                max_lines: Some(1),
+                ..Default::default()
+            },
+            highlight: Some("#ffd400".to_string()),
+        };
+        let cues = parse_json(WHISPER).unwrap();
+        let script = to_ass(&cues[..1], &settings, 1920, 1080);
+        // "Hola a" y "todos" no caben juntos en 8 letras: dos trozos, el primero
+        // hasta que empieza "todos". Cada uno es un solo evento: "Hola" se resalta
+        // de 0 a 0,5, "a" de 0,6 a 0,7 y "todos" desde el inicio de su trozo
+        assert!(script.contains(concat!(
+            r"Dialogue: 0,0:00:00.00,0:00:00.80,Captions,,0,0,0,,{\an7\pos(100,980)}",
+            r"{\1c&H00D4FF&\t(500,500,\1c&HFFFFFF&)}Hola ",
+            r"{\1c&HFFFFFF&\t(600,600,\1c&H00D4FF&)\t(700,700,\1c&HFFFFFF&)}a",
+            "\n"
+        )));
+        assert!(script.ends_with(concat!(
+            r"Dialogue: 0,0:00:00.80,0:00:02.00,Captions,,0,0,0,,{\an7\pos(100,980)}",
+            r"{\1c&H00D4FF&\t(800,800,\1c&HFFFFFF&)}todos",
+            "\n"
+        )));
+        // Sin capas encima, la opacidad del texto no cambia al decirse
+        assert_eq!(script.matches("Dialogue:").count(), 2);
+        assert_eq!(script.matches("\nStyle:").count(), 1);
+    }
+
+    #[test]
+    fn test_long_cues_are_rechunked() {
+        let settings = TextSettings {
+            font: "sans-serif".to_string(),
+            size: 50,
+            color: "white".to_string(),
+            x: "(w-text_w)/2".to_string(),
+            y: "h-100".to_string(),
+            max_width: Some(240),
+            max_lines: Some(1),
+            ..Default::default()
+        };
+        // Con la aproximación caben 8 letras por línea
+        let cues = [Cue {
+            start: 0.0,
+            end: 4.0,
+            text: "unos dos tres {1%}".to_string(),
+            words: Vec::new(),
+        }];
+        let script = to_ass(&cues, &CaptionSettings { style: settings, highlight: None }, 1920, 1080);
+        assert!(script.starts_with("[Script Info]\nScriptType: v4.00+\nPlayResX: 1920\nPlayResY: 1080\n"));
+        assert!(script.contains(
+            "Style: Captions,sans-serif,60.00,&H00FFFFFF,&H00FFFFFF,&H00000000,&H00000000,0,0,0,0,100,100,0,0,1,0,0,7,0,0,0,1\n"
+        ));
+        let events: Vec<&str> = script.lines().filter(|l| l.starts_with("Dialogue: ")).collect();
+        assert_eq!(
+            events,
+            [
+                r"Dialogue: 0,0:00:00.00,0:00:02.00,Captions,,0,0,0,,{\an7\pos(840,980)}unos dos",
+                r"Dialogue: 0,0:00:02.00,0:00:03.00,Captions,,0,0,0,,{\an7\pos(900,980)}tres",
+                r"Dialogue: 0,0:00:03.00,0:00:04.00,Captions,,0,0,0,,{\an7\pos(900,980)}\{1%\}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_style_and_effects() {
+        let settings: CaptionSettings = serde_yaml::from_str(
+            "font: Open Sans Bold\nsize: 40\ncolor: '#ffffff'\nx: (w-text_w)/2\ny: h-text_h-40\nalign: center\n\
+             max_width: 600\nbox: {color: black@0.5, padding: 10}\nshadow: {x: 2, y: 4}\nalpha: 0.5\n\
+             animation: {fade_in: 0.3, fade_out: 5}",
+        )
+        .unwrap();
+        let cues = [Cue { start: 1.0, end: 3.0, text: "uno dos tres".to_string(), words: Vec::new() }];
+        let script = to_ass(&cues, &settings, 1280, 720);
+        assert!(script.contains(
+            "Style: Captions,Open Sans,48.00,&H7FFFFFFF,&H7FFFFFFF,&HBF000000,&HB2000000,-1,0,0,0,100,100,0,0,3,10,0,7,0,0,0,1\n"
+        ));
+        // 12 letras de 24 píxeles centradas; el fundido de salida no pasa del evento
+        assert!(script.ends_with(
+            "Dialogue: 0,0:00:01.00,0:00:03.00,Captions,,0,0,0,,{\\an8\\pos(640,632)\\fad(300,2000)\\xshad2\\yshad4}uno dos tres\n"
+        ));
+    }
+
+    #[test]
+    fn test_subtitles_filter() {
+        let mut settings = CaptionSettings::default();
+        settings.style.font = "/fonts/Open Sans.ttf".to_string();
+        assert_eq!(
+            subtitles_filter(Path::new("/tmp/sub's.ass"), &settings, true),
+            r"subtitles=filename='/tmp/sub\'\''s.ass':fontsdir=/fonts:alpha=1"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let settings: CaptionSettings = serde_yaml::from_str(
+            "font: sans-serif\nsize: 40\ncolor: blanco\nx: w/2-t*10\ny: h-80\nhighlight: '#ffd400'\n\
+             animation: {typewriter: 2}",
+        )
+        .unwrap();
+        let problems = settings.validate();
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].contains("color ('blanco')"));
+        assert!(problems[1].starts_with("captions: x"));
+        assert!(problems[2].contains("typewriter"));
+    }
+}
@@ -1,9 +1,75 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use audiowave::OutputFormat;
+use audiowave::models::show::ShowSettings;
+use audiowave::placeholder::parse_var;
+use audiowave::preview::{PREVIEW_SECONDS, Preview, parse_timestamp};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(author = "Lorenzo Carbonell <atareao.es>", version, about = "Generador de videos con waveform a partir de MP3")]
-pub struct Args {
-    /// Archivo MP3 de entrada
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+
+    /// Activa el modo DEBUG
+    #[arg(long, global = true)]
+    pub debug: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Genera el video con la waveform
+    Render(Box<RenderArgs>),
+
+    /// Lista las plantillas de la configuración con su resolución y estilo
+    #[command(alias = "list-templates")]
+    Templates(ConfigArgs),
+
+    /// Lista los estilos de waveform disponibles
+    #[command(alias = "list-styles")]
+    Styles,
+
+    /// Comprueba la configuración y sus plantillas sin renderizar
+    Validate {
+        #[command(flatten)]
+        config: ConfigArgs,
+
+        /// Plantilla a validar (por defecto, todas)
+        #[arg(short, long)]
+        template: Option<String>,
+    },
+
+    /// Crea un archivo de configuración a partir de la plantilla por defecto
+    Init {
+        /// Ruta del archivo a crear (por defecto, ~/.config/audiowave/config.yml)
+        path: Option<String>,
+
+        /// Sobreescribe el archivo si ya existe
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    /// Archivo de configuración YAML
+    #[arg(short = 'c', long, default_value = "")]
+    pub config: String,
+}
+
+impl ConfigArgs {
+    pub fn path(&self) -> Option<String> {
+        if self.config.is_empty() {
+            None
+        } else {
+            Some(self.config.clone())
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    /// Audio de entrada (MP3, FLAC, Ogg, Opus, M4A, WAV...)
     #[arg(short, long)]
     pub input: String,
 
@@ -19,6 +85,22 @@ pub struct Args {
     #[arg(short, long)]
     pub subtitle: Option<String>,
 
+    /// Artista si el audio no lo trae (sobreescribe `show.artist`)
+    #[arg(long)]
+    pub default_artist: Option<String>,
+
+    /// Álbum si el audio no lo trae (sobreescribe `show.album`)
+    #[arg(long)]
+    pub default_album: Option<String>,
+
+    /// Fondo si el audio no tiene carátula (sobreescribe `show.background`)
+    #[arg(long)]
+    pub default_background: Option<String>,
+
+    /// Campo para los textos de la plantilla (ej. --var invitado="Ana"); se puede repetir
+    #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
     /// Rate
     #[arg(short, long)]
     pub rate: Option<u32>,
@@ -27,19 +109,77 @@ pub struct Args {
     #[arg(long)]
     pub wave_color: Option<String>,
 
-    /// Archivo de salida (mkv)
+    /// Archivo de salida; el formato se deduce de la extensión
     #[arg(short, long, default_value = "output.mkv")]
     pub output: String,
 
-    /// Archivo de configuración YAML
-    #[arg(short = 'c', long, default_value = "")]
-    pub config: String,
+    /// Formato de salida: mp4, mkv, webm, mov, gif, webp o png (secuencia)
+    #[arg(short = 'f', long, value_parser = clap::value_parser!(OutputFormat), conflicts_with = "frame")]
+    pub format: Option<OutputFormat>,
 
-    /// Modo de prueba: solo genera los primeros 5 segundos
-    #[arg(short, long)]
+    /// Solo la onda y los textos sobre fondo transparente (mov, webm o png)
+    #[arg(long)]
+    pub transparent: bool,
+
+    /// Subtítulos SRT, WebVTT o JSON de whisper (karaoke) que se queman en el video
+    #[arg(long, value_name = "FILE")]
+    pub captions: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    /// Modo de prueba: genera 5 segundos en calidad borrador
+    #[arg(short, long, conflicts_with = "frame")]
     pub preview: bool,
 
-    /// Activa el modo DEBUG
+    /// Instante del audio desde el que empezar (ej. '90', '1:30', '00:01:30.5')
+    #[arg(long, value_parser = parse_timestamp, conflicts_with = "frame")]
+    pub start: Option<f64>,
+
+    /// Duración a renderizar desde el inicio (mismo formato que --start)
+    #[arg(long, value_parser = parse_timestamp, conflicts_with = "frame")]
+    pub duration: Option<f64>,
+
+    /// Calidad borrador: codifica a mitad de resolución con un preset rápido (la
+    /// composición se hace a tamaño completo)
     #[arg(long)]
-    pub debug: bool,
+    pub draft: bool,
+
+    /// Guarda un único fotograma PNG del instante indicado
+    #[arg(long, value_parser = parse_timestamp)]
+    pub frame: Option<f64>,
+
+    /// Formato del progreso: barra en la terminal o un objeto JSON por línea en stdout
+    #[arg(long, value_enum, default_value_t = ProgressFormat::Text)]
+    pub progress_format: ProgressFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressFormat {
+    Text,
+    Json,
+}
+
+impl RenderArgs {
+    /// Valores de `show` indicados en la línea de comandos.
+    pub fn show(&self) -> ShowSettings {
+        ShowSettings {
+            title: None,
+            artist: self.default_artist.clone(),
+            album: self.default_album.clone(),
+            background: self.default_background.clone(),
+            vars: Default::default(),
+        }
+    }
+
+    pub fn preview(&self) -> Preview {
+        Preview {
+            start: self.start,
+            duration: self
+                .duration
+                .or(self.preview.then_some(PREVIEW_SECONDS)),
+            draft: self.draft || self.preview,
+            frame: self.frame,
+        }
+    }
 }
@@ -0,0 +1,136 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Errores que puede devolver audiowave. Cada variante tiene su propio código de salida
+/// (ver [`AudiowaveError::exit_code`] y el README).
+#[derive(Debug)]
+pub enum AudiowaveError {
+    /// Error de entrada/salida no asociado a otra variante.
+    Io(io::Error),
+    /// El archivo de configuración indicado no existe.
+    ConfigNotFound(PathBuf),
+    /// La configuración no se pudo leer o no es un YAML válido.
+    ConfigInvalid(String),
+    /// `init` no sobreescribe una configuración existente sin `--force`.
+    ConfigExists(PathBuf),
+    /// La plantilla pedida no está en la configuración.
+    TemplateNotFound(String),
+    /// No se pudieron extraer los metadatos del audio.
+    Metadata(String),
+    /// No se pudo lanzar FFmpeg (normalmente porque no está instalado).
+    FfmpegSpawn(io::Error),
+    /// FFmpeg terminó con un código distinto de cero.
+    FfmpegFailed {
+        code: Option<i32>,
+        /// Últimas líneas de log de FFmpeg.
+        log: Vec<String>,
+    },
+    /// `validate` encontró plantillas con problemas.
+    ValidationFailed(usize),
+    /// Falta algún dato obligatorio al construir un [`crate::RenderJob`].
+    InvalidJob(String),
+    /// El formato de salida no admite los códecs u opciones pedidos.
+    IncompatibleFormat(String),
+    /// Ninguna de las fuentes de una capa de texto existe.
+    FontNotFound(String),
+    /// El archivo de subtítulos no se pudo leer o no es SRT/WebVTT válido.
+    Captions(String),
+}
+
+pub type Result<T> = std::result::Result<T, AudiowaveError>;
+
+impl AudiowaveError {
+    /// Código de salida del proceso. El 2 lo reserva clap para errores de uso.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Io(_) => 1,
+            Self::ConfigNotFound(_) => 3,
+            Self::ConfigInvalid(_) => 4,
+            Self::TemplateNotFound(_) => 5,
+            Self::Metadata(_) => 6,
+            Self::FfmpegSpawn(_) => 7,
+            Self::FfmpegFailed { .. } => 8,
+            Self::ValidationFailed(_) => 9,
+            Self::ConfigExists(_) => 10,
+            Self::InvalidJob(_) => 11,
+            Self::IncompatibleFormat(_) => 12,
+            Self::FontNotFound(_) => 13,
+            Self::Captions(_) => 14,
+        }
+    }
+}
+
+impl fmt::Display for AudiowaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Error de entrada/salida: {e}"),
+            Self::ConfigNotFound(path) => {
+                write!(f, "No se encuentra el archivo de configuración {}", path.display())
+            }
+            Self::ConfigInvalid(msg) => write!(f, "Configuración no válida: {msg}"),
+            Self::ConfigExists(path) => write!(
+                f,
+                "El archivo {} ya existe (usa --force para sobreescribirlo)",
+                path.display()
+            ),
+            Self::TemplateNotFound(name) => write!(f, "Plantilla '{name}' no encontrada"),
+            Self::Metadata(msg) => write!(f, "No se pudieron leer los metadatos: {msg}"),
+            Self::FfmpegSpawn(e) => write!(f, "No se pudo ejecutar FFmpeg: {e}"),
+            Self::FfmpegFailed { code: Some(code), .. } => {
+                write!(f, "FFmpeg terminó con el código {code}")
+            }
+            Self::FfmpegFailed { code: None, .. } => {
+                write!(f, "FFmpeg terminó por una señal")
+            }
+            Self::ValidationFailed(count) => write!(f, "{count} plantilla(s) con errores"),
+            Self::InvalidJob(msg) => write!(f, "Trabajo de renderizado incompleto: {msg}"),
+            Self::IncompatibleFormat(msg) => write!(f, "Formato de salida no compatible: {msg}"),
+            Self::FontNotFound(msg) => write!(f, "Fuente no encontrada: {msg}"),
+            Self::Captions(msg) => write!(f, "No se pudieron leer los subtítulos: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for AudiowaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) | Self::FfmpegSpawn(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AudiowaveError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let errors = [
+            AudiowaveError::Io(io::Error::other("io")),
+            AudiowaveError::ConfigNotFound(PathBuf::from("config.yml")),
+            AudiowaveError::ConfigInvalid("yaml".to_string()),
+            AudiowaveError::ConfigExists(PathBuf::from("config.yml")),
+            AudiowaveError::TemplateNotFound("default".to_string()),
+            AudiowaveError::Metadata("id3".to_string()),
+            AudiowaveError::FfmpegSpawn(io::Error::other("ffmpeg")),
+            AudiowaveError::FfmpegFailed { code: Some(1), log: Vec::new() },
+            AudiowaveError::ValidationFailed(1),
+            AudiowaveError::InvalidJob("input".to_string()),
+            AudiowaveError::IncompatibleFormat("gif".to_string()),
+            AudiowaveError::FontNotFound("title".to_string()),
+            AudiowaveError::Captions("episodio.srt".to_string()),
+        ];
+        let mut codes: Vec<u8> = errors.iter().map(|e| e.exit_code()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(!codes.contains(&0));
+        assert!(!codes.contains(&2));
+    }
+}
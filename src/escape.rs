@@ -0,0 +1,178 @@
+//! Escapado de valores para los filtros de FFmpeg.
+//!
+//! Un texto atraviesa tres niveles antes de llegar a drawtext:
+//!
+//! 1. La expansión de drawtext, donde `\` escapa y `%{...}` se expande.
+//! 2. El valor de la opción (`clave=valor` separadas por `:`).
+//! 3. El filtergraph, donde `[ ] , ;` separan filtros y enlaces.
+
+/// Caracteres con significado en el nivel del filtergraph.
+const GRAPH_SPECIAL: &[char] = &['\\', '\'', '[', ']', ',', ';'];
+
+/// Espacios que FFmpeg recorta al principio y al final de un token.
+const WHITESPACE: &[char] = &[' ', '\n', '\t', '\r'];
+
+/// Nivel 1 para valores de datos (títulos, metadatos): todo se dibuja literalmente.
+pub fn drawtext_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || c == '%' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Nivel 1 para el texto fijo de una plantilla: se respetan `%{...}` y los escapes
+/// con `\`, pero un `%` suelto se dibuja tal cual en vez de romper el filtro.
+pub fn drawtext_literal(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '%' && chars.peek() != Some(&'{') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Nivel 2: `\`, `'` y `:` se escapan, y también los espacios de los extremos para
+/// que no se recorten.
+pub fn option_value(value: &str) -> String {
+    let leading = value.len() - value.trim_start_matches(WHITESPACE).len();
+    let trailing = value.trim_end_matches(WHITESPACE).len().max(leading);
+    let mut out = String::with_capacity(value.len());
+    for (i, c) in value.char_indices() {
+        let edge = WHITESPACE.contains(&c) && (i < leading || i >= trailing);
+        if edge || matches!(c, '\\' | '\'' | ':') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Nivel 3: entrecomilla un valor ya escapado para el nivel 2.
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Niveles 2 y 3 para una opción (colores, expresiones). Solo se entrecomilla si
+/// hace falta, para que las expresiones sencillas queden legibles.
+pub fn filter_arg(value: &str) -> String {
+    let value = option_value(value);
+    if value.contains(GRAPH_SPECIAL) {
+        quote(&value)
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Réplica de `av_get_token` de libavutil: lee un token hasta un carácter de
+    /// `term`, quitando comillas y escapes. Devuelve el token y el resto.
+    pub(crate) fn av_get_token<'a>(buf: &'a str, term: &str) -> (String, &'a str) {
+        let mut p = buf.trim_start_matches(WHITESPACE).chars().peekable();
+        let mut rest = buf.trim_start_matches(WHITESPACE);
+        let mut out = String::new();
+        // Longitud mínima que no se recorta (hasta el último escape o comilla)
+        let mut end = 0;
+        while let Some(&c) = p.peek() {
+            if term.contains(c) {
+                break;
+            }
+            p.next();
+            rest = &rest[c.len_utf8()..];
+            if c == '\\' && p.peek().is_some() {
+                let next = p.next().unwrap();
+                rest = &rest[next.len_utf8()..];
+                out.push(next);
+                end = out.len();
+            } else if c == '\'' {
+                loop {
+                    match p.next() {
+                        Some('\'') => {
+                            rest = &rest[1..];
+                            end = out.len();
+                            break;
+                        }
+                        Some(q) => {
+                            rest = &rest[q.len_utf8()..];
+                            out.push(q);
+                        }
+                        None => break,
+                    }
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        let trimmed = out[end..].trim_end_matches(WHITESPACE).len();
+        out.truncate(end + trimmed);
+        (out, rest)
+    }
+
+    /// Réplica de la expansión de drawtext sin funciones: `\x` → `x`; `%` es un error.
+    pub(crate) fn drawtext_expand(text: &str) -> Option<String> {
+        let mut out = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => out.push(chars.next().unwrap_or('\\')),
+                '%' => return None,
+                c => out.push(c),
+            }
+        }
+        Some(out)
+    }
+
+    /// Deshace los niveles 3 y 2 de un único valor.
+    fn unescape_arg(arg: &str) -> String {
+        let (graph, rest) = av_get_token(arg, "[],;");
+        assert!(rest.is_empty(), "el filtergraph cortó el valor: {arg}");
+        let (value, rest) = av_get_token(&graph, ":");
+        assert!(rest.is_empty(), "la opción cortó el valor: {graph}");
+        value
+    }
+
+    #[test]
+    fn test_known_cases() {
+        assert_eq!(drawtext_value(r"100% C:\temp"), r"100\% C:\\temp");
+        assert_eq!(drawtext_literal("100% %{pts}"), r"100\% %{pts}");
+        assert_eq!(option_value("Rust: what's new"), r"Rust\: what\'s new");
+        assert_eq!(quote(r"Rust\: what\'s new"), r"'Rust\: what\'\''s new'");
+        assert_eq!(filter_arg("(w-text_w)/2"), "(w-text_w)/2");
+        assert_eq!(filter_arg("if(gte(t,5),10,20)"), "'if(gte(t,5),10,20)'");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_filter_arg_round_trip(value in "[^\u{0}]*") {
+            prop_assert_eq!(unescape_arg(&filter_arg(&value)), value);
+        }
+
+        #[test]
+        fn prop_quoted_text_round_trip(value in "[^\u{0}]*") {
+            let arg = quote(&option_value(&drawtext_value(&value)));
+            prop_assert_eq!(drawtext_expand(&unescape_arg(&arg)), Some(value));
+        }
+
+        #[test]
+        fn prop_hostile_titles(
+            value in prop::collection::vec(
+                prop::sample::select(vec!["'", "\\", "%", ":", ",", ";", "[", "]", " ", "{", "}", "a", "é", "\n"]),
+                0..40,
+            )
+        ) {
+            let value = value.concat();
+            let arg = quote(&option_value(&drawtext_value(&value)));
+            prop_assert_eq!(drawtext_expand(&unescape_arg(&arg)), Some(value));
+        }
+    }
+}
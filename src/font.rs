@@ -0,0 +1,241 @@
+//! Resolución de fuentes por nombre de familia con fontconfig (`fc-match`).
+//!
+//! El campo `font` de una capa admite una ruta a un archivo o una familia con su
+//! estilo (`Ubuntu Bold`), y varias opciones separadas por comas que se prueban en
+//! orden: `"Ubuntu Bold, DejaVu Sans Bold, sans-serif"`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use ttf_parser::{Face, name_id};
+
+/// Familias genéricas: fontconfig siempre les asigna una fuente instalada.
+const GENERIC: &[&str] = &["sans-serif", "sans", "serif", "monospace", "mono"];
+
+/// Palabras que indican peso, inclinación o anchura al final del nombre.
+const STYLE_WORDS: &[&str] = &[
+    "thin", "hairline", "extralight", "ultralight", "light", "book", "regular", "normal",
+    "medium", "semibold", "demibold", "bold", "extrabold", "ultrabold", "black", "heavy",
+    "italic", "oblique", "condensed", "semicondensed", "expanded",
+];
+
+/// Una de las opciones de la cadena `font`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontSpec {
+    /// Archivo de fuente (`.ttf`, `.otf`...).
+    Path(PathBuf),
+    /// Familia y estilo para fontconfig.
+    Family { family: String, style: Option<String> },
+}
+
+impl FontSpec {
+    /// Separa `Ubuntu Bold Italic` en familia (`Ubuntu`) y estilo (`Bold Italic`).
+    /// Un patrón de fontconfig (`Ubuntu:style=Bold`) se respeta tal cual.
+    pub fn parse(entry: &str) -> FontSpec {
+        let entry = entry.trim();
+        if is_path(entry) {
+            return FontSpec::Path(PathBuf::from(entry));
+        }
+        if let Some((family, props)) = entry.split_once(':') {
+            let style = props.strip_prefix("style=").map(str::to_string);
+            return FontSpec::Family { family: family.trim().to_string(), style };
+        }
+        let words: Vec<&str> = entry.split_whitespace().collect();
+        let family_len = words
+            .iter()
+            .rposition(|w| !STYLE_WORDS.contains(&w.to_lowercase().as_str()))
+            .map_or(0, |i| i + 1);
+        let style = words[family_len..].join(" ");
+        FontSpec::Family {
+            family: words[..family_len].join(" "),
+            style: (!style.is_empty()).then_some(style),
+        }
+    }
+
+    /// Patrón para `fc-match` y para la opción `font` de drawtext.
+    pub fn pattern(&self) -> String {
+        match self {
+            FontSpec::Path(path) => path.to_string_lossy().into_owned(),
+            FontSpec::Family { family, style: Some(style) } => format!("{family}:style={style}"),
+            FontSpec::Family { family, style: None } => family.clone(),
+        }
+    }
+}
+
+/// Resultado de buscar la fuente de una capa.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FontMatch {
+    /// Una de las opciones existe.
+    Found(PathBuf),
+    /// Ninguna opción existe; fontconfig propone otra fuente en su lugar.
+    Substitute(PathBuf),
+    /// No hay `fc-match`: los nombres se pasan a FFmpeg sin comprobar.
+    Unchecked,
+    /// Ninguna opción existe y no hay sustituta.
+    Missing,
+}
+
+/// Separa la cadena `font` en sus opciones. Un archivo que existe no se separa
+/// aunque su ruta tenga comas.
+pub fn chain(font: &str) -> Vec<FontSpec> {
+    if Path::new(font).is_file() {
+        return vec![FontSpec::Path(PathBuf::from(font))];
+    }
+    font.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(FontSpec::parse)
+        .collect()
+}
+
+/// `true` si `font` apunta a un archivo en lugar de a una familia.
+pub fn is_path(font: &str) -> bool {
+    let lower = font.to_lowercase();
+    font.contains(['/', '\\'])
+        || [".ttf", ".otf", ".ttc", ".pfb", ".woff"].iter().any(|ext| lower.ends_with(ext))
+}
+
+/// Familia y estilo de una fuente, para pedírsela por nombre a libass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaceInfo {
+    pub family: String,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+impl FaceInfo {
+    /// Lee la familia que declara el archivo (la tipográfica si la tiene).
+    pub fn from_file(path: &Path) -> Option<FaceInfo> {
+        let data = fs::read(path).ok()?;
+        let face = Face::parse(&data, 0).ok()?;
+        let name = |id| {
+            face.names()
+                .into_iter()
+                .filter(|name| name.name_id == id && name.is_unicode())
+                .find_map(|name| name.to_string())
+        };
+        let family = name(name_id::TYPOGRAPHIC_FAMILY).or_else(|| name(name_id::FAMILY))?;
+        Some(FaceInfo { family, bold: face.is_bold(), italic: face.is_italic() })
+    }
+
+    /// Primera opción de `font`: el archivo si existe o la familia y su estilo.
+    pub fn of(font: &str) -> FaceInfo {
+        match chain(font).into_iter().next() {
+            Some(FontSpec::Path(path)) => Self::from_file(&path).unwrap_or_else(|| FaceInfo {
+                family: path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(),
+                bold: false,
+                italic: false,
+            }),
+            Some(FontSpec::Family { family, style }) => {
+                let style = style.unwrap_or_default().to_lowercase();
+                FaceInfo {
+                    family,
+                    bold: style.contains("bold") || style.contains("black") || style.contains("heavy"),
+                    italic: style.contains("italic") || style.contains("oblique"),
+                }
+            }
+            None => FaceInfo { family: "sans-serif".to_string(), bold: false, italic: false },
+        }
+    }
+}
+
+/// Prueba las opciones de `font` en orden.
+pub fn resolve(font: &str) -> FontMatch {
+    let specs = chain(font);
+    let mut fontconfig = true;
+    let mut substitute = None;
+    for spec in &specs {
+        match spec {
+            FontSpec::Path(path) if path.exists() => return FontMatch::Found(path.clone()),
+            FontSpec::Path(_) => {}
+            FontSpec::Family { family, .. } => match fc_match(&spec.pattern()) {
+                Some((file, families)) => {
+                    let generic = GENERIC.contains(&family.to_lowercase().as_str());
+                    if generic || families.iter().any(|f| f.eq_ignore_ascii_case(family)) {
+                        return FontMatch::Found(file);
+                    }
+                    substitute.get_or_insert(file);
+                }
+                None => fontconfig = false,
+            },
+        }
+    }
+    match substitute {
+        Some(file) => FontMatch::Substitute(file),
+        None if !fontconfig => FontMatch::Unchecked,
+        None => FontMatch::Missing,
+    }
+}
+
+/// Mejor coincidencia de fontconfig: archivo y familias. `None` si no hay `fc-match`.
+fn fc_match(pattern: &str) -> Option<(PathBuf, Vec<String>)> {
+    let output = Command::new("fc-match")
+        .args(["--format", "%{file}\n%{family}", pattern])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())?;
+    parse_match(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_match(output: &str) -> Option<(PathBuf, Vec<String>)> {
+    let (file, families) = output.split_once('\n').unwrap_or((output, ""));
+    let file = Path::new(file.trim());
+    if file.as_os_str().is_empty() {
+        return None;
+    }
+    let families = families.split(',').map(|f| f.trim().to_string()).collect();
+    Some((file.to_path_buf(), families))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn family(family: &str, style: Option<&str>) -> FontSpec {
+        FontSpec::Family { family: family.to_string(), style: style.map(str::to_string) }
+    }
+
+    #[test]
+    fn test_parse_family_and_style() {
+        assert_eq!(FontSpec::parse("Ubuntu Bold"), family("Ubuntu", Some("Bold")));
+        assert_eq!(FontSpec::parse("Open Sans SemiBold Italic"), family("Open Sans", Some("SemiBold Italic")));
+        assert_eq!(FontSpec::parse("DejaVu Sans Mono"), family("DejaVu Sans Mono", None));
+        assert_eq!(FontSpec::parse("Ubuntu:style=Medium"), family("Ubuntu", Some("Medium")));
+        assert_eq!(FontSpec::parse(" /fonts/Ubuntu-B.ttf "), FontSpec::Path(PathBuf::from("/fonts/Ubuntu-B.ttf")));
+        assert_eq!(FontSpec::parse("Ubuntu Bold").pattern(), "Ubuntu:style=Bold");
+    }
+
+    #[test]
+    fn test_chain_and_paths() {
+        let specs = chain("Ubuntu Bold, /no/existe/Ubuntu-B.ttf, sans-serif");
+        assert_eq!(specs.len(), 3);
+        assert!(matches!(specs[1], FontSpec::Path(_)));
+        assert!(is_path("Ubuntu-B.TTF"));
+        assert!(!is_path("Ubuntu Bold"));
+        assert_eq!(resolve("/no/existe/Ubuntu-B.ttf"), FontMatch::Missing);
+    }
+
+    #[test]
+    fn test_face_info() {
+        let info = FaceInfo::of("Open Sans SemiBold Italic, sans-serif");
+        assert_eq!(info, FaceInfo { family: "Open Sans".to_string(), bold: true, italic: true });
+        assert_eq!(FaceInfo::of("").family, "sans-serif");
+
+        let path = Path::new("/usr/share/fonts/truetype/dejavu/DejaVuSans-Bold.ttf");
+        if path.exists() {
+            let info = FaceInfo::from_file(path).unwrap();
+            assert_eq!(info.family, "DejaVu Sans");
+            assert!(info.bold && !info.italic);
+        }
+    }
+
+    #[test]
+    fn test_parse_match() {
+        let (file, families) =
+            parse_match("/usr/share/fonts/DejaVuSans-Bold.ttf\nDejaVu Sans,DejaVu Sans Bold").unwrap();
+        assert_eq!(file, PathBuf::from("/usr/share/fonts/DejaVuSans-Bold.ttf"));
+        assert_eq!(families, ["DejaVu Sans", "DejaVu Sans Bold"]);
+        assert_eq!(parse_match(""), None);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::error::{AudiowaveError, Result};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Contenedor de salida. Decide el muxer y los códecs por defecto.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Mp4,
+    Mkv,
+    Webm,
+    Mov,
+    Gif,
+    Webp,
+    /// Secuencia numerada de imágenes PNG.
+    Png,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 7] = [
+        Self::Mp4,
+        Self::Mkv,
+        Self::Webm,
+        Self::Mov,
+        Self::Gif,
+        Self::Webp,
+        Self::Png,
+    ];
+
+    /// Formato que corresponde a la extensión del archivo, si se conoce.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Self::ALL.into_iter().find(|f| f.extension() == extension)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mkv => "mkv",
+            Self::Webm => "webm",
+            Self::Mov => "mov",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+            Self::Png => "png",
+        }
+    }
+
+    /// Nombre del muxer de FFmpeg (`-f`).
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            Self::Mkv => "matroska",
+            Self::Png => "image2",
+            _ => self.extension(),
+        }
+    }
+
+    /// Los formatos animados de imagen no llevan pista de audio.
+    pub fn has_audio(&self) -> bool {
+        !matches!(self, Self::Gif | Self::Webp | Self::Png)
+    }
+
+    /// Códec y formato de píxel con canal alfa: ProRes 4444, VP9 con alfa o PNG RGBA.
+    /// `None` si el formato no puede guardar transparencia.
+    pub fn alpha_video_args(&self, draft: bool) -> Option<Vec<String>> {
+        let args: &[&str] = match self {
+            Self::Mov => &["-c:v", "prores_ks", "-profile:v", "4444", "-pix_fmt", "yuva444p10le"],
+            // libvpx necesita desactivar los alt-ref para codificar el alfa
+            Self::Webm if draft => &[
+                "-c:v", "libvpx-vp9", "-pix_fmt", "yuva420p", "-auto-alt-ref", "0",
+                "-deadline", "realtime", "-cpu-used", "8", "-crf", "40", "-b:v", "0",
+            ],
+            Self::Webm => &[
+                "-c:v", "libvpx-vp9", "-pix_fmt", "yuva420p", "-auto-alt-ref", "0", "-crf", "31",
+                "-b:v", "0",
+            ],
+            Self::Png => &["-c:v", "png", "-pix_fmt", "rgba"],
+            _ => return None,
+        };
+        Some(args.iter().map(|a| a.to_string()).collect())
+    }
+
+    pub fn default_video_codec(&self) -> &'static str {
+        match self {
+            Self::Mp4 | Self::Mkv | Self::Mov => "libx264",
+            Self::Webm => "libvpx-vp9",
+            Self::Gif => "gif",
+            Self::Webp => "libwebp_anim",
+            Self::Png => "png",
+        }
+    }
+
+    pub fn default_audio_codec(&self) -> Option<&'static str> {
+        match self {
+            Self::Mp4 | Self::Mkv | Self::Mov => Some("aac"),
+            Self::Webm => Some("libopus"),
+            Self::Gif | Self::Webp | Self::Png => None,
+        }
+    }
+
+    /// Códecs de video que admite el contenedor (`None` = cualquiera).
+    fn video_codecs(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Mp4 => Some(&[
+                "libx264", "libx265", "h264_nvenc", "hevc_nvenc", "h264_vaapi", "hevc_vaapi",
+                "h264_qsv", "hevc_qsv", "libaom-av1", "libsvtav1", "av1_nvenc", "mpeg4",
+            ]),
+            Self::Webm => Some(&["libvpx", "libvpx-vp9", "libaom-av1", "libsvtav1"]),
+            Self::Mov => Some(&[
+                "libx264", "libx265", "h264_videotoolbox", "hevc_videotoolbox", "prores",
+                "prores_ks", "prores_aw", "prores_videotoolbox", "mpeg4", "png", "qtrle",
+            ]),
+            Self::Gif => Some(&["gif"]),
+            Self::Webp => Some(&["libwebp_anim", "libwebp"]),
+            Self::Png => Some(&["png"]),
+            Self::Mkv => None,
+        }
+    }
+
+    /// Códecs de audio que admite el contenedor (`None` = cualquiera).
+    fn audio_codecs(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Mp4 => Some(&["aac", "libfdk_aac", "libmp3lame", "libopus", "ac3", "eac3", "alac"]),
+            Self::Webm => Some(&["libopus", "libvorbis"]),
+            Self::Mov => Some(&["aac", "libfdk_aac", "alac", "pcm_s16le", "pcm_s24le", "libmp3lame"]),
+            Self::Gif | Self::Webp | Self::Png => Some(&[]),
+            Self::Mkv => None,
+        }
+    }
+
+    /// Comprueba que los códecs pedidos caben en el contenedor. En los formatos sin
+    /// audio el códec de audio no se usa, así que no se comprueba: la misma
+    /// plantilla sirve para todos los formatos.
+    pub fn check_codecs(&self, video: Option<&str>, audio: Option<&str>) -> Result<()> {
+        if let (Some(codec), Some(allowed)) = (video, self.video_codecs())
+            && !allowed.contains(&codec)
+        {
+            return Err(AudiowaveError::IncompatibleFormat(format!(
+                "el códec de video '{codec}' no es compatible con {self}"
+            )));
+        }
+        if let (Some(codec), Some(allowed)) = (audio, self.audio_codecs())
+            && self.has_audio()
+            && !allowed.contains(&codec)
+        {
+            return Err(AudiowaveError::IncompatibleFormat(format!(
+                "el códec de audio '{codec}' no es compatible con {self}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Argumentos del muxer.
+    pub fn muxer_args(&self) -> Vec<String> {
+        let mut args = vec!["-f".to_string(), self.muxer().to_string()];
+        match self {
+            Self::Mp4 | Self::Mov => args.extend(["-movflags".to_string(), "+faststart".to_string()]),
+            Self::Gif | Self::Webp => args.extend(["-loop".to_string(), "0".to_string()]),
+            Self::Mkv | Self::Webm | Self::Png => {}
+        }
+        args
+    }
+
+    /// El GIF necesita una paleta propia para no degradar los colores.
+    pub fn apply_to_filter(&self, filter: String, label: &'static str) -> (String, &'static str) {
+        match self {
+            Self::Gif => (
+                format!(
+                    "{filter};{label}split[gif_a][gif_b];[gif_a]palettegen=stats_mode=diff[gif_pal];\
+                     [gif_b][gif_pal]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle[gif]"
+                ),
+                "[gif]",
+            ),
+            _ => (filter, label),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|f| f.extension() == name)
+            .ok_or_else(|| format!("Formato '{s}' desconocido (mp4, mkv, webm, mov, gif, webp, png)"))
+    }
+}
+
+/// Una secuencia PNG necesita un patrón numerado: `frames.png` pasa a `frames_%05d.png`.
+pub fn sequence_pattern(output: &Path) -> PathBuf {
+    if output.to_string_lossy().contains('%') {
+        return output.to_path_buf();
+    }
+    let stem = output
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "frame".to_string());
+    output.with_file_name(format!("{stem}_%05d.png"))
+}
+
+/// Formato final a partir de `--format` y de la extensión del archivo de salida.
+pub fn resolve(requested: Option<OutputFormat>, output: &Path) -> Result<Option<OutputFormat>> {
+    match (requested, OutputFormat::from_path(output)) {
+        (Some(requested), Some(inferred)) if requested != inferred => {
+            Err(AudiowaveError::IncompatibleFormat(format!(
+                "--format {requested} no coincide con la extensión de {}",
+                output.display()
+            )))
+        }
+        (requested, inferred) => Ok(requested.or(inferred)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(OutputFormat::from_path(Path::new("a/b.MP4")), Some(OutputFormat::Mp4));
+        assert_eq!(OutputFormat::from_path(Path::new("teaser.gif")), Some(OutputFormat::Gif));
+        assert_eq!(OutputFormat::from_path(Path::new("video.avi")), None);
+        assert_eq!(OutputFormat::from_path(Path::new("video")), None);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("webm".parse::<OutputFormat>().unwrap(), OutputFormat::Webm);
+        assert!("avi".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let out = PathBuf::from("out.webm");
+        assert_eq!(resolve(None, &out).unwrap(), Some(OutputFormat::Webm));
+        assert_eq!(resolve(Some(OutputFormat::Webm), &out).unwrap(), Some(OutputFormat::Webm));
+        assert!(resolve(Some(OutputFormat::Mp4), &out).is_err());
+        assert_eq!(
+            resolve(Some(OutputFormat::Mov), Path::new("out.bin")).unwrap(),
+            Some(OutputFormat::Mov)
+        );
+        assert_eq!(resolve(None, Path::new("out.bin")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_codecs() {
+        assert!(OutputFormat::Mp4.check_codecs(Some("libx265"), Some("aac")).is_ok());
+        assert!(OutputFormat::Webm.check_codecs(Some("libx264"), None).is_err());
+        assert!(OutputFormat::Webm.check_codecs(None, Some("aac")).is_err());
+        // Los formatos sin audio ignoran el códec de audio de la plantilla
+        assert!(OutputFormat::Gif.check_codecs(None, Some("aac")).is_ok());
+        assert!(OutputFormat::Png.check_codecs(None, Some("libopus")).is_ok());
+        assert!(OutputFormat::Mkv.check_codecs(Some("ffv1"), Some("flac")).is_ok());
+    }
+
+    #[test]
+    fn test_gif_palette() {
+        let (filter, label) = OutputFormat::Gif.apply_to_filter("[x]null[outv]".to_string(), "[outv]");
+        assert!(filter.starts_with("[x]null[outv];[outv]split[gif_a][gif_b];"));
+        assert!(filter.contains("palettegen"));
+        assert_eq!(label, "[gif]");
+
+        let (filter, label) = OutputFormat::Mp4.apply_to_filter("f".to_string(), "[outv]");
+        assert_eq!((filter.as_str(), label), ("f", "[outv]"));
+    }
+
+    #[test]
+    fn test_alpha_args() {
+        let args = OutputFormat::Mov.alpha_video_args(false).unwrap();
+        assert_eq!(args, ["-c:v", "prores_ks", "-profile:v", "4444", "-pix_fmt", "yuva444p10le"]);
+        let args = OutputFormat::Webm.alpha_video_args(false).unwrap();
+        assert!(args.windows(2).any(|w| w == ["-pix_fmt", "yuva420p"]));
+        assert!(OutputFormat::Mp4.alpha_video_args(false).is_none());
+    }
+
+    #[test]
+    fn test_sequence_pattern() {
+        assert_eq!(sequence_pattern(Path::new("out/frames.png")), Path::new("out/frames_%05d.png"));
+        assert_eq!(sequence_pattern(Path::new("f_%03d.png")), Path::new("f_%03d.png"));
+    }
+
+    #[test]
+    fn test_muxer_args() {
+        assert_eq!(OutputFormat::Mp4.muxer_args(), ["-f", "mp4", "-movflags", "+faststart"]);
+        assert_eq!(OutputFormat::Mkv.muxer_args(), ["-f", "matroska"]);
+        assert_eq!(OutputFormat::Gif.muxer_args(), ["-f", "gif", "-loop", "0"]);
+    }
+}
@@ -0,0 +1,245 @@
+//! Ajuste de textos largos: mide el texto con la fuente, lo parte en líneas y
+//! reduce el tamaño hasta que el bloque cabe en `max_width` y `max_lines`.
+
+use log::warn;
+use std::{fs, path::Path};
+use ttf_parser::{Face, GlyphId};
+
+/// Métricas para medir texto a un tamaño dado.
+#[derive(Debug, Clone)]
+pub enum Metrics {
+    /// Archivo de fuente leído con `ttf-parser`.
+    Font(Vec<u8>),
+    /// Aproximación cuando no hay archivo (fuente por nombre sin `fc-match`).
+    Approx,
+}
+
+impl Metrics {
+    /// Lee la fuente; si no es un archivo válido usa la aproximación.
+    pub fn load(path: &Path) -> Metrics {
+        match fs::read(path) {
+            Ok(data) if Face::parse(&data, 0).is_ok() => Metrics::Font(data),
+            _ => {
+                warn!("No se pudo leer la fuente {}; el ajuste será aproximado", path.display());
+                Metrics::Approx
+            }
+        }
+    }
+
+    /// Ancho en píxeles de `text` (sin escapes de drawtext) a `size` píxeles.
+    pub fn width(&self, text: &str, size: u32) -> f64 {
+        match self {
+            Metrics::Font(data) => {
+                let face = Face::parse(data, 0).expect("Fuente validada al cargarla");
+                let units: u32 = text
+                    .chars()
+                    .map(|c| {
+                        let glyph = face.glyph_index(c).unwrap_or(GlyphId(0));
+                        face.glyph_hor_advance(glyph).unwrap_or(0) as u32
+                    })
+                    .sum();
+                units as f64 * size as f64 / face.units_per_em() as f64
+            }
+            Metrics::Approx => text.chars().count() as f64 * size as f64 * 0.6,
+        }
+    }
+
+    /// Alto de la celda (ascendente más descendente) a `size` píxeles. Es lo que
+    /// libass entiende por tamaño de fuente.
+    pub fn cell_height(&self, size: u32) -> f64 {
+        match self {
+            Metrics::Font(data) => {
+                let face = Face::parse(data, 0).expect("Fuente validada al cargarla");
+                let units = face.ascender() as i32 - face.descender() as i32;
+                units as f64 * size as f64 / face.units_per_em() as f64
+            }
+            Metrics::Approx => size as f64 * 1.2,
+        }
+    }
+
+    /// Distancia entre líneas consecutivas a `size` píxeles.
+    pub fn line_height(&self, size: u32) -> f64 {
+        match self {
+            Metrics::Font(data) => {
+                let face = Face::parse(data, 0).expect("Fuente validada al cargarla");
+                let units = face.ascender() as i32 - face.descender() as i32 + face.line_gap() as i32;
+                units as f64 * size as f64 / face.units_per_em() as f64
+            }
+            Metrics::Approx => size as f64 * 1.2,
+        }
+    }
+}
+
+/// Texto ya repartido en líneas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// Tamaño de fuente elegido.
+    pub size: u32,
+    /// Líneas, con el escapado de drawtext intacto.
+    pub lines: Vec<String>,
+    /// Ancho de cada línea en píxeles.
+    pub widths: Vec<f64>,
+    pub line_height: f64,
+}
+
+impl Block {
+    pub fn width(&self) -> f64 {
+        self.widths.iter().cloned().fold(0.0, f64::max)
+    }
+
+    pub fn height(&self) -> f64 {
+        self.line_height * self.lines.len() as f64
+    }
+}
+
+/// Busca el mayor tamaño entre `min_size` y `size` con el que `text` cabe en
+/// `max_lines` líneas de `max_width` píxeles. Si ni con `min_size` cabe, la última
+/// línea se corta con `…`.
+///
+/// `text` viene escapado para drawtext: los cortes se hacen en los espacios y los
+/// escapes se ignoran al medir.
+pub fn fit(
+    text: &str,
+    metrics: &Metrics,
+    size: u32,
+    min_size: u32,
+    max_width: u32,
+    max_lines: Option<u32>,
+) -> Block {
+    let max_width = max_width as f64;
+    let max_lines = max_lines.map_or(usize::MAX, |n| n.max(1) as usize);
+    let min_size = min_size.clamp(1, size.max(1));
+    for candidate in (min_size..=size.max(min_size)).rev() {
+        let lines = wrap(text, metrics, candidate, max_width);
+        let fits = lines.len() <= max_lines
+            && lines.iter().all(|line| metrics.width(&visible(line), candidate) <= max_width);
+        if fits {
+            return block(lines, metrics, candidate);
+        }
+    }
+
+    let mut lines = wrap(text, metrics, min_size, max_width);
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.last_mut().expect("max_lines es al menos 1");
+        *last = ellipsize(last, metrics, min_size, max_width);
+    }
+    block(lines, metrics, min_size)
+}
+
+fn block(lines: Vec<String>, metrics: &Metrics, size: u32) -> Block {
+    let widths = lines.iter().map(|line| metrics.width(&visible(line), size)).collect();
+    Block { size, lines, widths, line_height: metrics.line_height(size) }
+}
+
+/// Reparto voraz por palabras; los saltos de línea del texto se respetan.
+pub(crate) fn wrap(text: &str, metrics: &Metrics, size: u32, max_width: f64) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if !line.is_empty() && metrics.width(&visible(&candidate), size) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Recorta `line` hasta que quepa con `…` al final.
+fn ellipsize(line: &str, metrics: &Metrics, size: u32, max_width: f64) -> String {
+    let mut chars: Vec<char> = line.chars().collect();
+    loop {
+        let text: String = chars.iter().collect();
+        let text = text.trim_end();
+        let candidate = format!("{text}…");
+        if chars.is_empty() || metrics.width(&visible(&candidate), size) <= max_width {
+            return candidate;
+        }
+        chars.pop();
+        // No se deja un escape a medias
+        let slashes = chars.iter().rev().take_while(|&&c| c == '\\').count();
+        if slashes % 2 == 1 {
+            chars.pop();
+        }
+    }
+}
+
+/// Texto tal como se dibuja: sin las barras de escape de drawtext.
+pub(crate) fn visible(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_text_keeps_size() {
+        let block = fit("Hola", &Metrics::Approx, 60, 30, 1000, Some(2));
+        assert_eq!(block.size, 60);
+        assert_eq!(block.lines, ["Hola"]);
+        assert_eq!(block.width(), 4.0 * 36.0);
+    }
+
+    #[test]
+    fn test_wraps_before_shrinking() {
+        // Con la aproximación son 30 píxeles por carácter a tamaño 50
+        let block = fit("uno dos tres cuatro", &Metrics::Approx, 50, 20, 300, Some(3));
+        assert_eq!(block.size, 50);
+        assert_eq!(block.lines, ["uno dos", "tres", "cuatro"]);
+        assert_eq!(block.height(), 180.0);
+    }
+
+    #[test]
+    fn test_shrinks_until_it_fits() {
+        let block = fit("uno dos tres cuatro", &Metrics::Approx, 50, 20, 300, Some(2));
+        assert!(block.size < 50);
+        assert_eq!(block.lines.len(), 2);
+        assert!(block.width() <= 300.0);
+    }
+
+    #[test]
+    fn test_ellipsis_at_min_size() {
+        let block = fit("uno dos tres cuatro cinco seis", &Metrics::Approx, 50, 50, 300, Some(1));
+        assert_eq!(block.size, 50);
+        assert_eq!(block.lines.len(), 1);
+        assert!(block.lines[0].ends_with('…'));
+        assert!(block.width() <= 300.0);
+    }
+
+    #[test]
+    fn test_escapes_are_not_measured() {
+        assert_eq!(visible(r"100\% C\:\\x"), r"100% C:\x");
+        let block = fit(r"50\%", &Metrics::Approx, 10, 10, 1000, None);
+        assert_eq!(block.width(), 18.0);
+    }
+
+    #[test]
+    fn test_font_metrics() {
+        let path = Path::new("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf");
+        if !path.exists() {
+            return;
+        }
+        let metrics = Metrics::load(path);
+        assert!(matches!(metrics, Metrics::Font(_)));
+        assert!(metrics.width("MMMM", 40) > metrics.width("iiii", 40));
+        assert!(metrics.line_height(40) > 40.0);
+    }
+}
@@ -0,0 +1,47 @@
+//! Generación de videos con waveform a partir de un audio.
+//!
+//! ```no_run
+//! use audiowave::{Config, RenderEvent, RenderJob, Renderer};
+//!
+//! # async fn example() -> audiowave::Result<()> {
+//! let config = Config::load(None).await?;
+//! let job = RenderJob::builder(config.template("default")?.clone())
+//!     .input("episodio.mp3")
+//!     .title("Episodio 1")
+//!     .subtitle("Mi podcast")
+//!     .output("episodio.mkv")
+//!     .build()?;
+//!
+//! let mut handle = Renderer::new().render(job);
+//! while let Some(event) = handle.next_event().await {
+//!     if let RenderEvent::Progress(update) = event {
+//!         println!("{:.0}%", update.percent);
+//!     }
+//! }
+//! let output = handle.await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod ass;
+pub mod backend;
+pub mod captions;
+pub mod error;
+pub mod escape;
+pub mod font;
+pub mod format;
+pub mod layout;
+pub mod models;
+pub mod placeholder;
+pub mod preview;
+pub mod progress;
+pub mod render;
+
+pub use backend::{FfmpegBackend, MediaBackend};
+pub use error::{AudiowaveError, Result};
+pub use format::OutputFormat;
+pub use models::{AudioMetadata, Config};
+pub use models::template::Template;
+pub use preview::Preview;
+pub use progress::ProgressUpdate;
+pub use render::{RenderEvent, RenderHandle, RenderJob, RenderJobBuilder, Renderer};
@@ -1,27 +1,23 @@
 mod cli;
-mod models;
 
+use audiowave::{
+    AudioMetadata, AudiowaveError, Config, ProgressUpdate, RenderEvent, RenderJob, Renderer, Result,
+    captions,
+    models::style::CATALOG,
+    placeholder,
+    render::output_name_from_title,
+};
 use clap::Parser;
-use cli::Args;
+use cli::{Cli, Commands, ConfigArgs, ProgressFormat, RenderArgs};
 use indicatif::{ProgressBar, ProgressStyle};
-use models::{AudioMetadata, Config};
-use regex::Regex;
-use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    process::Command,
-};
-use std::{
-    error::Error,
-    process::Stdio,
-    collections::VecDeque
-};
-use log::{debug, error, info};
+use std::{path::PathBuf, process::ExitCode};
+use log::{error, info};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
     // Configurar el nivel de log antes de inicializar el logger
-    let log_level = if args.debug {
+    let log_level = if cli.debug {
         log::LevelFilter::Debug
     } else {
         log::LevelFilter::Info // O el nivel que prefieras por defecto
@@ -30,146 +26,297 @@ async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::Builder::new()
         .filter_level(log_level)
         .init();
-    let config_path = if args.config.is_empty() {
-        None
-    } else {
-        Some(args.config.clone())
+
+    match run(cli.command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("❌ {e}");
+            if let AudiowaveError::FfmpegFailed { log, .. } = &e {
+                error!("--- ÚLTIMOS LOGS DE ERROR ---");
+                for line in log {
+                    error!("  > {}", line);
+                }
+                error!("-----------------------------");
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+async fn run(command: Commands) -> Result<()> {
+    match command {
+        Commands::Render(args) => render(*args).await,
+        Commands::Templates(config) => list_templates(config).await,
+        Commands::Styles => {
+            list_styles();
+            Ok(())
+        }
+        Commands::Validate { config, template } => validate(config, template).await,
+        Commands::Init { path, force } => {
+            let path = Config::init(path.map(PathBuf::from), force)?;
+            println!("✨ Configuración creada en: {}", path.display());
+            Ok(())
+        }
+    }
+}
+
+async fn list_templates(config: ConfigArgs) -> Result<()> {
+    let config = Config::load(config.path()).await?;
+    let mut names: Vec<&String> = config.templates.keys().collect();
+    names.sort();
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+
+    for name in names {
+        let template = &config.templates[name];
+        let style = template
+            .waveform
+            .style
+            .map(|s| s.name())
+            .unwrap_or("pipeline");
+        println!(
+            "{name:<width$}  {w}x{h}  {style}",
+            w = template.video.width,
+            h = template.video.height,
+        );
+    }
+    Ok(())
+}
+
+fn list_styles() {
+    let width = CATALOG.iter().map(|i| i.name.len()).max().unwrap_or(0);
+    for info in CATALOG {
+        println!(
+            "{name:<width$}  {color:<26}  {description}",
+            name = info.name,
+            color = info.default_color.unwrap_or("-"),
+            description = info.description,
+        );
+    }
+}
+
+async fn validate(config: ConfigArgs, template: Option<String>) -> Result<()> {
+    let config = Config::load(config.path()).await?;
+    let mut names: Vec<&String> = match &template {
+        Some(name) => vec![
+            config
+                .templates
+                .get_key_value(name)
+                .ok_or_else(|| AudiowaveError::TemplateNotFound(name.clone()))?
+                .0,
+        ],
+        None => config.templates.keys().collect(),
     };
-    let config = Config::load(config_path).await?;
-    let mut template = config
-        .templates
-        .get(&args.template)
-        .ok_or_else(|| format!("Plantilla '{}' no encontrada", args.template))?
-        .clone();
+    names.sort();
+
+    let mut failed = 0;
+    for name in names {
+        let template = &config.templates[name];
+        let mut problems = template.validate();
+        problems.extend(config.show_for(template).validate());
+        if problems.is_empty() {
+            println!("✅ {name}");
+        } else {
+            failed += 1;
+            println!("❌ {name}");
+            for problem in problems {
+                println!("   - {problem}");
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(AudiowaveError::ValidationFailed(failed));
+    }
+    Ok(())
+}
+
+async fn render(args: RenderArgs) -> Result<()> {
+    let json = args.progress_format == ProgressFormat::Json;
+    // En modo JSON stdout queda reservado para los eventos
+    let status = |msg: String| {
+        if !json {
+            println!("{msg}");
+        }
+    };
+
+    let config = Config::load(args.config.path()).await?;
+    let mut template = config.template(&args.template)?.clone();
 
     if let Some(rate) = args.rate {
         template.waveform.rate = Some(rate as i32);
     }
 
-    if let Some(color) = args.wave_color {
+    if let Some(color) = args.wave_color.clone() {
         template.waveform.color = Some(color);
     }
 
-    println!("🔍 Analizando archivo y metadatos...");
-    let meta = AudioMetadata::new(args.input.clone()).await;
+    status("🔍 Analizando archivo y metadatos...".to_string());
+    let mut meta = AudioMetadata::new(args.input.clone()).await?;
+    let show = config.show_for(&template).merge(&args.show());
+    meta.artist = meta.artist.or(show.artist);
+    meta.album = meta.album.or(show.album);
 
-    let title = args.title.clone().unwrap_or(meta.title);
-    let subtitle = args.subtitle.clone().unwrap_or(meta.artist);
+    // Campos de los textos: `show.vars`, después los metadatos que no estén vacíos
+    // y por último `--var`
+    let mut fields = show.vars;
+    for (key, value) in meta.fields() {
+        if !value.is_empty() || !fields.contains_key(&key) {
+            fields.insert(key, value);
+        }
+    }
+    fields.extend(args.vars.iter().cloned());
+
+    // Prioridad: línea de comandos, etiquetas del audio, `show` y, para el título,
+    // el nombre del archivo
+    let title = resolve_title(args.title.clone(), meta.title, show.title, &args.input, &fields);
+    fields.insert("title".to_string(), title.clone());
+    let subtitle = args.subtitle.clone().or(meta.artist).unwrap_or_default();
+    // Si la plantilla no usa la carátula, el archivo temporal se borra aquí
+    let cover = meta.cover.filter(|_| template.background.use_cover);
+
+    let preview = args.preview();
+    let captions = match &args.captions {
+        Some(path) => captions::load(path).await?,
+        None => Vec::new(),
+    };
 
     let output_file = if args.output == "output.mkv" || args.output.is_empty() {
-        let safe_title = title.to_lowercase()
-            .replace(|c: char| !c.is_alphanumeric(), "_")
-            .replace("__", "_");
-        format!("{}.mkv", safe_title.trim_matches('_'))
+        let extension = match (preview.frame, args.format) {
+            (Some(_), _) => "png",
+            (None, Some(format)) => format.extension(),
+            (None, None) if args.transparent => "mov",
+            (None, None) => "mkv",
+        };
+        output_name_from_title(&title, extension)
     } else {
-        args.output.clone()
+        placeholder::expand_path(&args.output, &fields)
     };
 
-    let background = meta
-        .cover_path
-        .map(|p| p.to_string_lossy().into_owned())
-        .unwrap_or_else(|| template.background.path.clone());
-
-    let filter = template.build_filter_complex(&title, &subtitle);
+    let job = RenderJob::builder(template)
+        .input(&args.input)
+        .output(&output_file)
+        .fields(fields)
+        .title(title.clone())
+        .subtitle(subtitle)
+        .cover(cover)
+        .background(show.background.map(PathBuf::from))
+        .preview(preview)
+        .fps(args.rate)
+        .format(args.format)
+        .transparent(args.transparent)
+        .captions(captions)
+        .build()?;
 
     // --- Configuración de la Barra de Progreso ---
-    let pb = ProgressBar::new(100);
+    let pb = if json {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(100)
+    };
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}% ({eta})")?
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}% {msg}")
+            .expect("Plantilla de la barra de progreso no válida")
             .progress_chars("#>-"),
     );
 
-    let mut ffmpeg_cmd = Command::new("ffmpeg");
-    ffmpeg_cmd
-        .arg("-y")
-        .arg("-progress").arg("pipe:2")
-        .arg("-v").arg("info")
-        .arg("-loop").arg("1")
-        .arg("-i").arg(&background)
-        .arg("-i").arg(&args.input)
-        .arg("-filter_complex").arg(filter)
-        .arg("-map").arg("[outv]")
-        .arg("-map").arg("1:a")
-        .arg("-r").arg(args.rate.unwrap_or(30).to_string())
-        .arg("-c:v").arg("libx264")
-        .arg("-preset").arg("slow")
-        .arg("-crf").arg("18")
-        .arg("-pix_fmt").arg("yuv420p")
-        .arg("-movflags").arg("+faststart")
-        .arg("-shortest").arg(&output_file)
-        .stderr(Stdio::piped());
-
-    // Imprimir el comando generado como debug antes de spawn
-    debug!("🚀 Ejecutando comando: {:?}", ffmpeg_cmd);
-
-    let mut child = ffmpeg_cmd.spawn()?;
-
-    let stderr = child
-        .stderr
-        .take()
-        .ok_or("No se pudo capturar stderr de FFmpeg")?;
-    let mut reader = BufReader::new(stderr).lines();
-
-    // Regex para capturar Duration y time= de FFmpeg
-    let re_duration = Regex::new(r"Duration: (\d{2}):(\d{2}):(\d{2})")?;
-    let re_time = Regex::new(r"time=(\d{2}):(\d{2}):(\d{2})")?;
-
-    let mut total_seconds = 0f64;
-    // Búfer para guardar las últimas 15 líneas de log en caso de error
-    let mut error_logs: VecDeque<String> = VecDeque::with_capacity(15);
-
-    println!("🎬 Renderizando: {}...", title);
-
-    // Bucle asíncrono para leer el progreso
-    while let Ok(Some(line)) = reader.next_line().await {
-        // Guardar línea en el búfer de logs (mantiene las últimas 15)
-        debug!("Log FFmpeg: {}", line);
-        if error_logs.len() >= 15 {
-            error_logs.pop_front();
+    status(format!("🎬 Renderizando: {}...", title));
+
+    let mut handle = Renderer::new().render(job);
+    while let Some(event) = handle.next_event().await {
+        if json {
+            println!("{}", serde_json::to_string(&event).expect("Evento serializable"));
+            continue;
         }
-        error_logs.push_back(line.clone());
-        // 1. Intentar capturar la duración total al inicio
-        if total_seconds == 0.0
-            && let Some(caps) = re_duration.captures(&line)
-        {
-            let h: f64 = caps[1].parse()?;
-            let m: f64 = caps[2].parse()?;
-            let s: f64 = caps[3].parse()?;
-            total_seconds = h * 3600.0 + m * 60.0 + s;
-            debug!("⏱ Duración total: {} segundos", total_seconds);
+        if let RenderEvent::Progress(update) = event {
+            pb.set_position(update.percent as u64);
+            pb.set_message(progress_message(&update));
         }
+    }
 
-        // 2. Capturar el tiempo actual de renderizado
-        if let Some(caps) = re_time.captures(&line)
-            && total_seconds > 0.0
-        {
-            let h: f64 = caps[1].parse()?;
-            let m: f64 = caps[2].parse()?;
-            let s: f64 = caps[3].parse()?;
-            let current_seconds = h * 3600.0 + m * 60.0 + s;
-
-            let percent = (current_seconds / total_seconds * 100.0) as u64;
-            pb.set_position(percent);
-            debug!("Progreso: {}%", percent);
+    let result = handle.await;
+    if json {
+        let event = match &result {
+            Ok(output) => serde_json::json!({ "event": "done", "output": output }),
+            Err(e) => serde_json::json!({
+                "event": "error",
+                "code": e.exit_code(),
+                "message": e.to_string(),
+            }),
+        };
+        println!("{event}");
+    }
+
+    match result {
+        Ok(output) => {
+            pb.finish_with_message("¡Completado!");
+            info!("✅ Video guardado en: {}", output.display());
+            Ok(())
+        }
+        Err(e) => {
+            pb.abandon();
+            Err(e)
         }
     }
+}
 
-    let status = child.wait().await?;
-    pb.finish_with_message("¡Completado!");
+/// Título del vídeo. Solo `show.title` es un texto con formato (admite campos
+/// como `{episode}`); el de la línea de comandos y el de las etiquetas se usan
+/// tal cual.
+fn resolve_title(
+    cli: Option<String>,
+    tag: Option<String>,
+    show: Option<String>,
+    input: &str,
+    fields: &placeholder::Fields,
+) -> String {
+    cli.or(tag)
+        .or_else(|| show.map(|title| placeholder::expand(&title, fields)))
+        .unwrap_or_else(|| {
+            PathBuf::from(input)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        })
+}
 
-    if status.success() {
-        pb.finish_with_message("¡Completado!");
-        info!("✅ Video guardado en: {}", output_file);
-    } else {
-        pb.abandon();
-        error!("\n❌ FFmpeg falló catastróficamente.");
-        error!("--- ÚLTIMOS LOGS DE ERROR ---");
-        for log in error_logs {
-            error!("  > {}", log);
+/// Velocidad y tiempo restante para la barra de progreso (ej. "2.5x · ETA 00:01:30").
+fn progress_message(update: &ProgressUpdate) -> String {
+    let speed = update
+        .speed
+        .map(|s| format!("{s:.1}x"))
+        .unwrap_or_else(|| "-".to_string());
+    match update.eta {
+        Some(eta) => {
+            let eta = eta.round() as u64;
+            format!(
+                "{speed} · ETA {:02}:{:02}:{:02}",
+                eta / 3600,
+                eta % 3600 / 60,
+                eta % 60
+            )
         }
-        error!("-----------------------------");
+        None => speed,
     }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_title_keeps_values_literal() {
+        let fields = placeholder::Fields::from([
+            ("x".to_string(), "1".to_string()),
+            ("episode".to_string(), "7".to_string()),
+        ]);
+        let literal = "Set {x} vs {{y}}".to_string();
+        assert_eq!(resolve_title(Some(literal.clone()), None, None, "in.mp3", &fields), literal);
+        assert_eq!(resolve_title(None, Some(literal.clone()), None, "in.mp3", &fields), literal);
+        assert_eq!(
+            resolve_title(None, None, Some("Episodio {episode}".to_string()), "in.mp3", &fields),
+            "Episodio 7"
+        );
+        assert_eq!(resolve_title(None, None, None, "/tmp/ep 7.mp3", &fields), "ep 7");
+    }
 }
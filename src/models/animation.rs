@@ -0,0 +1,197 @@
+use serde::Deserialize;
+
+/// Animaciones de una capa de texto. Los tiempos se cuentan desde `start` y se
+/// traducen a expresiones de drawtext (`alpha`, `x`, `y` y `enable`).
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct AnimationSettings {
+    /// Segundos de aparición gradual desde `start`.
+    pub fade_in: Option<f64>,
+    /// Segundos de desaparición gradual antes de `end`.
+    pub fade_out: Option<f64>,
+    /// Entrada deslizando desde un borde del video.
+    pub slide: Option<SlideSettings>,
+    /// Segundos que tarda en escribirse el texto letra a letra.
+    pub typewriter: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct SlideSettings {
+    pub from: Edge,
+    /// Segundos que dura el desplazamiento.
+    pub duration: f64,
+}
+
+impl Default for SlideSettings {
+    fn default() -> Self {
+        Self { from: Edge::Left, duration: 1.0 }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl AnimationSettings {
+    /// Expresión `alpha` con los fundidos, multiplicada por la opacidad `base`.
+    pub fn alpha_expr(&self, base: Option<f64>, start: f64, end: Option<f64>) -> Option<String> {
+        let mut terms = Vec::new();
+        if let Some(fade_in) = self.fade_in {
+            terms.push(format!("({})/{fade_in}", elapsed(start)));
+        }
+        if let (Some(fade_out), Some(end)) = (self.fade_out, end) {
+            terms.push(format!("({end}-t)/{fade_out}"));
+        }
+        let fade = match terms.as_slice() {
+            [] => return base.map(|alpha| alpha.to_string()),
+            [term] => format!("clip({term},0,1)"),
+            [a, b] => format!("clip(min({a},{b}),0,1)"),
+            _ => unreachable!("como mucho hay dos fundidos"),
+        };
+        Some(match base {
+            Some(alpha) => format!("{alpha}*{fade}"),
+            None => fade,
+        })
+    }
+
+    /// Expresiones `x` e `y` con el deslizamiento aplicado.
+    pub fn slide_exprs(&self, x: &str, y: &str, start: f64) -> (String, String) {
+        let Some(slide) = &self.slide else {
+            return (x.to_string(), y.to_string());
+        };
+        // Desplazamiento restante con frenado al final (ease-out cúbico)
+        let rest = format!("pow(1-clip(({})/{},0,1),3)", elapsed(start), slide.duration);
+        match slide.from {
+            Edge::Left => (format!("({x})-(({x})+text_w)*{rest}"), y.to_string()),
+            Edge::Right => (format!("({x})+(w-({x}))*{rest}"), y.to_string()),
+            Edge::Top => (x.to_string(), format!("({y})-(({y})+text_h)*{rest}")),
+            Edge::Bottom => (x.to_string(), format!("({y})+(h-({y}))*{rest}")),
+        }
+    }
+
+    /// Problemas de la animación; `window` es la duración visible si hay `end`.
+    pub fn validate(&self, name: &str, end: Option<f64>, window: Option<f64>) -> Vec<String> {
+        let mut problems = Vec::new();
+        let durations = [
+            ("fade_in", self.fade_in),
+            ("fade_out", self.fade_out),
+            ("slide.duration", self.slide.as_ref().map(|s| s.duration)),
+            ("typewriter", self.typewriter),
+        ];
+        for (field, value) in durations {
+            if value.is_some_and(|v| v <= 0.0) {
+                problems.push(format!("{name}: animation.{field} debe ser mayor que 0"));
+            }
+        }
+        if self.fade_out.is_some() && end.is_none() {
+            problems.push(format!("{name}: animation.fade_out necesita end"));
+        }
+        if let Some(window) = window
+            && self.fade_in.unwrap_or(0.0) + self.fade_out.unwrap_or(0.0) > window
+        {
+            problems.push(format!(
+                "{name}: los fundidos duran más que la ventana visible ({window}s)"
+            ));
+        }
+        problems
+    }
+}
+
+/// Segundos transcurridos desde `start`. Tras desplazar la capa por una búsqueda
+/// (`--start`, `--frame`), `start` puede ser negativo.
+fn elapsed(start: f64) -> String {
+    if start < 0.0 {
+        format!("t+{}", -start)
+    } else {
+        format!("t-{start}")
+    }
+}
+
+/// Parte un texto escapado para drawtext en letras visibles: un escape (`\x`) o
+/// una expansión (`%{...}`) cuentan como una sola.
+pub fn reveal_units(text: &str) -> Vec<&str> {
+    let mut units = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let len = if rest.starts_with("%{") {
+            rest.find('}').map_or(rest.len(), |i| i + 1)
+        } else if c == '\\' {
+            1 + rest[1..].chars().next().map_or(0, char::len_utf8)
+        } else {
+            c.len_utf8()
+        };
+        units.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alpha_expr() {
+        let anim = AnimationSettings {
+            fade_in: Some(1.0),
+            fade_out: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            anim.alpha_expr(None, 0.0, Some(10.0)).unwrap(),
+            "clip(min((t-0)/1,(10-t)/2),0,1)"
+        );
+        assert_eq!(
+            anim.alpha_expr(Some(0.8), 5.0, None).unwrap(),
+            "0.8*clip((t-5)/1,0,1)"
+        );
+        assert_eq!(AnimationSettings::default().alpha_expr(Some(0.5), 0.0, None).unwrap(), "0.5");
+        assert_eq!(AnimationSettings::default().alpha_expr(None, 0.0, None), None);
+    }
+
+    #[test]
+    fn test_slide_exprs() {
+        let anim = AnimationSettings {
+            slide: Some(SlideSettings { from: Edge::Bottom, duration: 0.5 }),
+            ..Default::default()
+        };
+        assert_eq!(
+            anim.slide_exprs("10", "600", 2.0),
+            ("10".to_string(), "(600)+(h-(600))*pow(1-clip((t-2)/0.5,0,1),3)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_negative_start_after_seek() {
+        let anim = AnimationSettings {
+            fade_in: Some(1.0),
+            slide: Some(SlideSettings { from: Edge::Left, duration: 0.5 }),
+            ..Default::default()
+        };
+        assert_eq!(anim.alpha_expr(None, -5.0, None).unwrap(), "clip((t+5)/1,0,1)");
+        assert_eq!(anim.slide_exprs("0", "0", -5.0).0, "(0)-((0)+text_w)*pow(1-clip((t+5)/0.5,0,1),3)");
+    }
+
+    #[test]
+    fn test_reveal_units() {
+        assert_eq!(reveal_units(r"a\%b%{pts}é"), ["a", r"\%", "b", "%{pts}", "é"]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let anim = AnimationSettings {
+            fade_in: Some(6.0),
+            fade_out: Some(5.0),
+            typewriter: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(anim.validate("title", None, None).len(), 2);
+        assert_eq!(anim.validate("title", Some(10.0), Some(10.0)).len(), 2);
+    }
+}
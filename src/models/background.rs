@@ -1,21 +1,348 @@
+use super::grading::{BACKGROUND_BRIGHTNESS, BACKGROUND_SATURATION, Grading};
+use crate::escape;
+use regex::Regex;
 use serde::Deserialize;
+use std::sync::LazyLock;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct BackgroundSettings {
+    /// Imagen, video o GIF de fondo; vacío para usar `generate`.
+    #[serde(default)]
     pub path: String,
-    pub mode: String, // "stretch", "fit", "fill"
+    #[serde(default = "default_mode")]
+    pub mode: String, // "stretch", "fit", "fill", "cover_blur"
+    /// Copia nítida de la imagen en el modo `cover_blur`.
+    #[serde(default)]
+    pub cover: CoverSettings,
+    /// Fondo generado con FFmpeg cuando no hay imagen ni carátula.
+    #[serde(default)]
+    pub generate: Option<GeneratedBackground>,
+    /// Usa la carátula del audio como fondo si la tiene.
+    #[serde(default = "default_use_cover")]
+    pub use_cover: bool,
+    /// Retoque del fondo (`brightness`, `contrast`, `saturation`, `blur`,
+    /// `vignette`, `lut`, `grain`). Sin indicarlo se oscurece un poco.
+    #[serde(flatten)]
+    pub grading: Grading,
 }
 
+fn default_mode() -> String {
+    "fit".to_string()
+}
+
+fn default_use_cover() -> bool {
+    true
+}
+
+impl Default for BackgroundSettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            mode: default_mode(),
+            cover: CoverSettings::default(),
+            generate: None,
+            use_cover: default_use_cover(),
+            grading: Grading::default(),
+        }
+    }
+}
+
+/// Modo `cover_blur`: la imagen desenfocada y oscurecida llena el cuadro y encima
+/// va la misma imagen nítida, recortada en cuadrado. El retoque del fondo
+/// (`brightness`, `lut`...) se aplica solo a la parte desenfocada.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CoverSettings {
+    /// Lado en píxeles de la copia nítida; por defecto, la mitad del alto del video.
+    pub size: Option<u32>,
+    /// Posición como en `overlay`: `W`/`H` son el video y `w`/`h` la copia nítida.
+    pub x: String,
+    pub y: String,
+    /// Radio de las esquinas redondeadas en píxeles.
+    pub radius: u32,
+    /// Intensidad del desenfoque del fondo (`sigma` de `gblur`).
+    pub blur: f64,
+    pub shadow: Option<CoverShadow>,
+}
+
+impl Default for CoverSettings {
+    fn default() -> Self {
+        Self {
+            size: None,
+            x: "(W-w)/2".to_string(),
+            y: "(H-h)/2".to_string(),
+            radius: 0,
+            blur: 30.0,
+            shadow: None,
+        }
+    }
+}
+
+/// Sombra negra difuminada bajo la copia nítida.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct CoverShadow {
+    /// Píxeles que se extiende el difuminado.
+    pub blur: u32,
+    pub x: i32,
+    pub y: i32,
+    pub opacity: f64,
+}
+
+impl Default for CoverShadow {
+    fn default() -> Self {
+        Self { blur: 20, x: 0, y: 12, opacity: 0.6 }
+    }
+}
+
+/// Brillo y saturación por defecto del fondo desenfocado de `cover_blur`, más
+/// oscuro que el de los demás modos para separarlo de la copia nítida.
+const COVER_BRIGHTNESS: f64 = -0.25;
+const COVER_SATURATION: f64 = 0.8;
+
+/// `w` y `h` en las expresiones de posición de la copia nítida.
+static COVER_SIZE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b[wh]\b").expect("Regex válida"));
+
+/// Fondos que genera FFmpeg con fuentes `lavfi`, sin necesidad de una imagen.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GeneratedBackground {
+    /// Color liso.
+    Color { color: String },
+    /// Degradado lineal entre 2 y 8 colores; `angle` en grados (0 = de izquierda a
+    /// derecha, 90 = de arriba abajo).
+    Linear {
+        colors: Vec<String>,
+        #[serde(default)]
+        angle: f64,
+    },
+    /// Degradado radial desde el centro hacia las esquinas.
+    Radial { colors: Vec<String> },
+    /// Ruido animado sobre un color; `strength` de 0 a 100.
+    Noise {
+        #[serde(default = "default_noise_color")]
+        color: String,
+        #[serde(default = "default_noise_strength")]
+        strength: u32,
+    },
+}
+
+fn default_noise_color() -> String {
+    "#202028".to_string()
+}
+
+fn default_noise_strength() -> u32 {
+    20
+}
+
+/// Extensiones que se tratan como video y se repiten en bucle.
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "m4v", "mov", "mkv", "webm", "avi", "gif"];
+
+/// Indica si el fondo es un video o un GIF animado en vez de una imagen fija.
+pub fn is_video(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Argumentos de entrada del fondo: una imagen fija se repite con `-loop 1` a los
+/// `fps` del video y un video se reproduce en bucle sin su audio. `-shortest` lo
+/// corta con el audio.
+pub fn input_args(path: &str, fps: u32) -> Vec<String> {
+    let mut args: Vec<String> = if is_video(path) {
+        vec!["-stream_loop".into(), "-1".into(), "-an".into()]
+    } else {
+        vec!["-loop".into(), "1".into(), "-framerate".into(), fps.to_string()]
+    };
+    args.extend(["-i".into(), path.to_string()]);
+    args
+}
+
+/// Fondo cuando no hay imagen ni `generate`.
+pub const DEFAULT_BACKGROUND_COLOR: &str = "black";
+
 impl BackgroundSettings {
-    pub fn to_filter(&self, v_width: u32, v_height: u32) -> String {
-        match self.mode.as_str() {
-            "fill" => format!("scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},eq=brightness=-0.1:saturation=0.95", w=v_width, h=v_height),
-            "fit" => format!("scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,eq=brightness=-0.1:saturation=0.95", w=v_width, h=v_height),
-            _ => format!("scale={w}:{h},eq=brightness=-0.1:saturation=0.95", w=v_width, h=v_height), // stretch
+    /// Cadena de escalado y retoque del fondo. `still` indica que la entrada es
+    /// una imagen fija repetida con `-loop 1`.
+    pub fn to_filter(&self, v_width: u32, v_height: u32, still: bool) -> String {
+        let scale = match self.mode.as_str() {
+            "fill" => format!("scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h}", w=v_width, h=v_height),
+            // El retoque va solo al fondo desenfocado; la copia nítida queda intacta
+            "cover_blur" => return self.cover_blur_filter(v_width, v_height, still),
+            "fit" => format!("scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2", w=v_width, h=v_height),
+            _ => format!("scale={w}:{h}", w=v_width, h=v_height), // stretch
+        };
+        match self.grading.to_filter(BACKGROUND_BRIGHTNESS, BACKGROUND_SATURATION) {
+            grading if grading.is_empty() => scale,
+            grading => format!("{scale},{grading}"),
+        }
+    }
+
+    /// Cadena del modo `cover_blur`. Usa etiquetas propias y termina sin etiqueta
+    /// para encajar en `[0:v]...[bg]` igual que los demás modos.
+    ///
+    /// Con una imagen fija el fondo se compone una sola vez y `loop` repite ese
+    /// fotograma: los desenfoques y el `geq` de las esquinas no se recalculan en
+    /// cada fotograma. El grano cambia con el tiempo, así que con `grain` no se repite.
+    fn cover_blur_filter(&self, v_width: u32, v_height: u32, still: bool) -> String {
+        let cover = &self.cover;
+        let size = cover.size.unwrap_or(v_height / 2);
+        // La copia nítida es cuadrada, así que `w` y `h` se conocen de antemano
+        let x = COVER_SIZE.replace_all(&cover.x, size.to_string().as_str());
+        let y = COVER_SIZE.replace_all(&cover.y, size.to_string().as_str());
+
+        let mut art = format!(
+            "[cb_art]scale={size}:{size}:force_original_aspect_ratio=increase,crop={size}:{size},format=rgba"
+        );
+        if cover.radius > 0 {
+            // Transparente fuera del arco de cada esquina
+            let r = cover.radius;
+            art.push_str(&format!(
+                ",geq=r='r(X,Y)':g='g(X,Y)':b='b(X,Y)':\
+                 a='if(gt(hypot(max(0,max({r}-X,X-W+1+{r})),max(0,max({r}-Y,Y-H+1+{r}))),{r}),0,alpha(X,Y))'"
+            ));
+        }
+
+        let mut chain = format!(
+            "split=2[cb_back][cb_art];\
+             [cb_back]scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},\
+             gblur=sigma={blur}{grading}[cb_blur];",
+            w = v_width,
+            h = v_height,
+            blur = cover.blur,
+            grading = match self.grading.to_filter(COVER_BRIGHTNESS, COVER_SATURATION) {
+                grading if grading.is_empty() => grading,
+                grading => format!(",{grading}"),
+            },
+        );
+        match &cover.shadow {
+            Some(shadow) => {
+                let pad = shadow.blur * 2;
+                chain.push_str(&format!(
+                    "{art},split=2[cb_sharp][cb_sh];\
+                     [cb_sh]colorchannelmixer=rr=0:gg=0:bb=0:aa={opacity},\
+                     pad=iw+{pad2}:ih+{pad2}:{pad}:{pad}:color=black@0,gblur=sigma={sigma}[cb_shadow];\
+                     [cb_blur][cb_shadow]overlay=({x})-{pad}+({dx}):({y})-{pad}+({dy})[cb_under];\
+                     [cb_under][cb_sharp]overlay={x}:{y}",
+                    opacity = shadow.opacity,
+                    pad2 = pad * 2,
+                    sigma = shadow.blur as f64 / 2.0,
+                    dx = shadow.x,
+                    dy = shadow.y,
+                ));
+            }
+            None => chain.push_str(&format!("{art}[cb_sharp];[cb_blur][cb_sharp]overlay={x}:{y}")),
+        }
+        if still && self.grading.grain.is_none_or(|g| g == 0) {
+            chain.push_str(",trim=end_frame=1,loop=loop=-1:size=1");
+        }
+        chain
+    }
+
+    /// Fuente `lavfi` del fondo generado (un color liso si no hay `generate`).
+    pub fn to_source(&self, width: u32, height: u32, fps: u32) -> String {
+        let color = GeneratedBackground::Color {
+            color: DEFAULT_BACKGROUND_COLOR.to_string(),
+        };
+        self.generate.as_ref().unwrap_or(&color).to_source(width, height, fps)
+    }
+
+    /// Problemas del fondo; `v_height` es el alto del video, del que sale el lado
+    /// de la carátula cuando no se indica `cover.size`.
+    pub fn validate(&self, v_height: u32) -> Vec<String> {
+        let mut problems = self.grading.validate("background");
+        if !["stretch", "fit", "fill", "cover_blur"].contains(&self.mode.as_str()) {
+            problems.push(format!(
+                "background: modo '{}' desconocido (stretch, fit, fill, cover_blur)",
+                self.mode
+            ));
+        }
+        if !self.path.is_empty() && !std::path::Path::new(&self.path).exists() {
+            problems.push(format!("background: no existe el archivo '{}'", self.path));
+        }
+        if self.cover.size == Some(0) {
+            problems.push("background: cover.size debe ser mayor que 0".to_string());
+        }
+        let size = self.cover.size.unwrap_or(v_height / 2);
+        if size > 0 && self.cover.radius > size / 2 {
+            problems.push(format!(
+                "background: cover.radius ({}) no puede superar la mitad del lado de la carátula ({size})",
+                self.cover.radius
+            ));
+        }
+        if let Some(shadow) = &self.cover.shadow
+            && !(0.0..=1.0).contains(&shadow.opacity)
+        {
+            problems.push(format!(
+                "background: cover.shadow.opacity ({}) debe estar entre 0 y 1",
+                shadow.opacity
+            ));
+        }
+        match &self.generate {
+            Some(GeneratedBackground::Linear { colors, .. } | GeneratedBackground::Radial { colors })
+                if !(2..=8).contains(&colors.len()) =>
+            {
+                problems.push(format!(
+                    "background: un degradado necesita entre 2 y 8 colores ({} indicados)",
+                    colors.len()
+                ));
+            }
+            Some(GeneratedBackground::Noise { strength, .. }) if *strength > 100 => {
+                problems.push(format!("background: strength ({strength}) debe estar entre 0 y 100"));
+            }
+            _ => {}
         }
+        problems
     }
 }
 
+impl GeneratedBackground {
+    pub fn to_source(&self, width: u32, height: u32, fps: u32) -> String {
+        let size = format!("s={width}x{height}:r={fps}");
+        match self {
+            GeneratedBackground::Color { color } => {
+                format!("color=c={}:{size}", escape::filter_arg(color))
+            }
+            GeneratedBackground::Linear { colors, angle } => {
+                let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let (dx, dy) = (cos * cx, sin * cy);
+                format!(
+                    "gradients={size}:{}:type=linear:x0={:.0}:y0={:.0}:x1={:.0}:y1={:.0}:speed=0",
+                    gradient_colors(colors),
+                    cx - dx,
+                    cy - dy,
+                    cx + dx,
+                    cy + dy
+                )
+            }
+            GeneratedBackground::Radial { colors } => format!(
+                "gradients={size}:{}:type=radial:x0={}:y0={}:x1=0:y1=0:speed=0",
+                gradient_colors(colors),
+                width / 2,
+                height / 2
+            ),
+            GeneratedBackground::Noise { color, strength } => format!(
+                "color=c={}:{size},noise=alls={strength}:allf=t+u",
+                escape::filter_arg(color)
+            ),
+        }
+    }
+}
+
+/// Opciones `c0`...`c7` y `nb_colors` de la fuente `gradients`.
+fn gradient_colors(colors: &[String]) -> String {
+    let mut options: Vec<String> = colors
+        .iter()
+        .take(8)
+        .enumerate()
+        .map(|(i, color)| format!("c{i}={}", escape::filter_arg(color)))
+        .collect();
+    options.push(format!("nb_colors={}", colors.len().clamp(2, 8)));
+    options.join(":")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,8 +352,9 @@ mod tests {
         let settings = BackgroundSettings {
             path: "test.png".to_string(),
             mode: "stretch".to_string(),
+            ..Default::default()
         };
-        assert_eq!(settings.to_filter(1920, 1080), "scale=1920:1080,eq=brightness=-0.1:saturation=0.95");
+        assert_eq!(settings.to_filter(1920, 1080, true), "scale=1920:1080,eq=brightness=-0.1:saturation=0.95");
     }
 
     #[test]
@@ -34,8 +362,9 @@ mod tests {
         let settings = BackgroundSettings {
             path: "test.png".to_string(),
             mode: "fit".to_string(),
+            ..Default::default()
         };
-        assert_eq!(settings.to_filter(1920, 1080), "scale=1920:1080:force_original_aspect_ratio=decrease,pad=1920:1080:(ow-iw)/2:(oh-ih)/2,eq=brightness=-0.1:saturation=0.95");
+        assert_eq!(settings.to_filter(1920, 1080, true), "scale=1920:1080:force_original_aspect_ratio=decrease,pad=1920:1080:(ow-iw)/2:(oh-ih)/2,eq=brightness=-0.1:saturation=0.95");
     }
 
     #[test]
@@ -43,7 +372,118 @@ mod tests {
         let settings = BackgroundSettings {
             path: "test.png".to_string(),
             mode: "fill".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(settings.to_filter(1920, 1080, true), "scale=1920:1080:force_original_aspect_ratio=increase,crop=1920:1080,eq=brightness=-0.1:saturation=0.95");
+    }
+
+    #[test]
+    fn test_grading_fields() {
+        let settings: BackgroundSettings =
+            serde_yaml::from_str("mode: stretch\nbrightness: 0\nsaturation: 1\nvignette: 0.3").unwrap();
+        assert_eq!(settings.to_filter(1280, 720, true), "scale=1280:720,vignette=angle=0.4712");
+
+        let undimmed: BackgroundSettings =
+            serde_yaml::from_str("mode: fill\nbrightness: 0\nsaturation: 1").unwrap();
+        assert_eq!(
+            undimmed.to_filter(1280, 720, true),
+            "scale=1280:720:force_original_aspect_ratio=increase,crop=1280:720"
+        );
+
+        let cover: BackgroundSettings =
+            serde_yaml::from_str("mode: cover_blur\nbrightness: 0\nsaturation: 1").unwrap();
+        assert!(cover.to_filter(1280, 720, true).contains(",gblur=sigma=30[cb_blur];"));
+    }
+
+    #[test]
+    fn test_cover_blur_filter() {
+        let settings: BackgroundSettings = serde_yaml::from_str(
+            "mode: cover_blur\ncover: { size: 600, radius: 24, shadow: { blur: 10 } }",
+        )
+        .unwrap();
+        assert!(settings.validate(1080).is_empty());
+        let filter = settings.to_filter(1920, 1080, true);
+        assert!(filter.starts_with("split=2[cb_back][cb_art];[cb_back]scale=1920:1080:force_original_aspect_ratio=increase,crop=1920:1080,gblur=sigma=30,"));
+        assert!(filter.contains("[cb_art]scale=600:600:force_original_aspect_ratio=increase,crop=600:600,format=rgba,geq="));
+        assert!(filter.contains("max(24-X,X-W+1+24)"));
+        assert!(filter.contains("pad=iw+40:ih+40:20:20:color=black@0,gblur=sigma=5[cb_shadow]"));
+        assert!(filter.contains("overlay=((W-600)/2)-20+(0):((H-600)/2)-20+(12)[cb_under]"));
+        // Imagen fija: se compone una vez y se repite
+        assert!(filter.ends_with("[cb_under][cb_sharp]overlay=(W-600)/2:(H-600)/2,trim=end_frame=1,loop=loop=-1:size=1"));
+        let video = settings.to_filter(1920, 1080, false);
+        assert!(video.ends_with("[cb_under][cb_sharp]overlay=(W-600)/2:(H-600)/2"));
+        let grain = BackgroundSettings { grading: Grading { grain: Some(10), ..Default::default() }, ..settings };
+        assert!(!grain.to_filter(1920, 1080, true).contains("loop="));
+
+        let plain = BackgroundSettings { mode: "cover_blur".to_string(), ..Default::default() };
+        assert!(plain.to_filter(1280, 720, false).ends_with(
+            "[cb_art]scale=360:360:force_original_aspect_ratio=increase,crop=360:360,format=rgba[cb_sharp];\
+             [cb_blur][cb_sharp]overlay=(W-360)/2:(H-360)/2"
+        ));
+    }
+
+    #[test]
+    fn test_validate_cover() {
+        let settings = BackgroundSettings {
+            mode: "cover_blur".to_string(),
+            cover: CoverSettings { size: Some(100), radius: 80, ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(settings.validate(1080).len(), 1);
+
+        // Sin `size` el lado es la mitad del alto del video
+        let settings = BackgroundSettings {
+            mode: "cover_blur".to_string(),
+            cover: CoverSettings { radius: 200, ..Default::default() },
+            ..Default::default()
+        };
+        assert!(settings.validate(1080).is_empty());
+        assert!(settings.validate(720)[0].contains("cover.radius (200)"));
+    }
+
+    #[test]
+    fn test_input_args() {
+        assert_eq!(input_args("fondo.png", 30), ["-loop", "1", "-framerate", "30", "-i", "fondo.png"]);
+        assert_eq!(input_args("bucle.MP4", 30), ["-stream_loop", "-1", "-an", "-i", "bucle.MP4"]);
+        assert_eq!(input_args("ondas.gif", 30), ["-stream_loop", "-1", "-an", "-i", "ondas.gif"]);
+    }
+
+    #[test]
+    fn test_generated_sources() {
+        let color = GeneratedBackground::Color { color: "#1e1e2e".to_string() };
+        assert_eq!(color.to_source(1920, 1080, 30), "color=c=#1e1e2e:s=1920x1080:r=30");
+
+        let linear = GeneratedBackground::Linear {
+            colors: vec!["#1e1e2e".to_string(), "#89b4fa".to_string()],
+            angle: 90.0,
+        };
+        assert_eq!(
+            linear.to_source(1920, 1080, 30),
+            "gradients=s=1920x1080:r=30:c0=#1e1e2e:c1=#89b4fa:nb_colors=2:type=linear:x0=960:y0=0:x1=960:y1=1080:speed=0"
+        );
+
+        let noise: GeneratedBackground = serde_yaml::from_str("type: noise\nstrength: 35").unwrap();
+        assert_eq!(
+            noise.to_source(1280, 720, 25),
+            "color=c=#202028:s=1280x720:r=25,noise=alls=35:allf=t+u"
+        );
+    }
+
+    #[test]
+    fn test_empty_path_falls_back_to_generated() {
+        let settings: BackgroundSettings = serde_yaml::from_str("path: ''").unwrap();
+        assert_eq!(settings.mode, "fit");
+        assert!(settings.use_cover);
+        assert!(settings.validate(1080).is_empty());
+        assert_eq!(settings.to_source(640, 360, 30), "color=c=black:s=640x360:r=30");
+    }
+
+    #[test]
+    fn test_validate_gradient_colors() {
+        let settings = BackgroundSettings {
+            generate: Some(GeneratedBackground::Radial { colors: vec!["red".to_string()] }),
+            ..Default::default()
         };
-        assert_eq!(settings.to_filter(1920, 1080), "scale=1920:1080:force_original_aspect_ratio=increase,crop=1920:1080,eq=brightness=-0.1:saturation=0.95");
+        assert_eq!(settings.validate(1080).len(), 1);
     }
 }
@@ -1,4 +1,6 @@
+use super::show::ShowSettings;
 use super::template::Template;
+use crate::error::{AudiowaveError, Result};
 use directories::ProjectDirs;
 use log::debug;
 use serde::Deserialize;
@@ -7,13 +9,16 @@ use std::{env, fs, path::PathBuf};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    /// Valores por defecto del programa para todas las plantillas.
+    #[serde(default, alias = "defaults")]
+    pub show: ShowSettings,
     pub templates: HashMap<String, Template>,
 }
 
 const DEFAULT_YAML: &str = include_str!("../../assets/default_config.yml");
 
 impl Config {
-    pub async fn load(path: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn load(path: Option<String>) -> Result<Self> {
         debug!("Cargando configuración...");
         let config_path = match path {
             Some(p) => {
@@ -21,7 +26,7 @@ impl Config {
                 if pb.exists() {
                     pb
                 } else {
-                    return Err("Config file not found in provided path".into());
+                    return Err(AudiowaveError::ConfigNotFound(pb));
                 }
             }
             None => {
@@ -30,27 +35,66 @@ impl Config {
             }
         };
 
-        let content = tokio::fs::read_to_string(config_path).await?;
-        serde_yaml::from_str(&content).map_err(|e| e.into())
+        let content = tokio::fs::read_to_string(&config_path).await?;
+        serde_yaml::from_str(&content).map_err(|e| {
+            AudiowaveError::ConfigInvalid(format!("{}: {e}", config_path.display()))
+        })
+    }
+
+    /// Devuelve la plantilla con ese nombre.
+    pub fn template(&self, name: &str) -> Result<&Template> {
+        self.templates
+            .get(name)
+            .ok_or_else(|| AudiowaveError::TemplateNotFound(name.to_string()))
+    }
+
+    /// Valores del programa para `template`: los de la plantilla sobre los globales.
+    pub fn show_for(&self, template: &Template) -> ShowSettings {
+        self.show.merge(&template.show)
+    }
+
+    /// Escribe la configuración por defecto en `path` (o en el directorio XDG del usuario).
+    /// Si el archivo ya existe solo se sobreescribe cuando `force` es verdadero.
+    pub fn init(path: Option<PathBuf>, force: bool) -> Result<PathBuf> {
+        let config_path = match path {
+            Some(p) => p,
+            None => Self::user_config_path()?,
+        };
+
+        if config_path.exists() && !force {
+            return Err(AudiowaveError::ConfigExists(config_path));
+        }
+
+        if let Some(parent) = config_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&config_path, DEFAULT_YAML)?;
+        Ok(config_path)
+    }
+
+    /// Ruta de la configuración del usuario (~/.config/audiowave/config.yml)
+    fn user_config_path() -> Result<PathBuf> {
+        let proj_dirs = ProjectDirs::from("es", "atareao", "audiowave").ok_or_else(|| {
+            AudiowaveError::ConfigInvalid(
+                "No se pudo determinar el directorio de configuración del usuario".to_string(),
+            )
+        })?;
+        Ok(proj_dirs.config_dir().join("config.yml"))
     }
 
     /// Busca el archivo o lo crea a partir del recurso embebido si no lo encuentra
-    fn get_or_create_config() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    fn get_or_create_config() -> Result<PathBuf> {
         debug!("Buscando archivo de configuración...");
         if let Some(path) = Self::find_config_file() {
             return Ok(path);
         }
 
         // Si no existe, lo creamos en el directorio XDG del usuario
-        let proj_dirs = ProjectDirs::from("es", "atareao", "audiowave")
-            .ok_or("No se pudo determinar el directorio de configuración del usuario")?;
-
-        let config_dir = proj_dirs.config_dir();
-        let config_path = config_dir.join("config.yml");
-
+        let config_path = Self::user_config_path()?;
         if !config_path.exists() {
-            fs::create_dir_all(config_dir)?;
-            fs::write(&config_path, DEFAULT_YAML)?;
+            Self::init(Some(config_path.clone()), false)?;
             debug!(
                 "✨ Configuración no encontrada. Se ha creado una por defecto en: {:?}",
                 config_path
@@ -95,6 +139,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::placeholder::Fields;
     use std::fs::File;
     use std::io::Write;
     use tempfile::tempdir;
@@ -137,11 +182,75 @@ mod tests {
             .unwrap();
         assert!(config.templates.contains_key("default"));
         assert_eq!(config.templates.get("default").unwrap().video.width, 1920);
+        assert_eq!(config.templates.get("default").unwrap().video.fps, Some(30));
+        assert_eq!(config.show, ShowSettings::default());
+    }
+
+    #[test]
+    fn test_show_defaults_and_template_override() {
+        let yaml = "
+defaults:
+  title: Nuevo Episodio
+  artist: Mi podcast
+templates:
+  default:
+    video: {width: 1280, height: 720}
+    background: {path: '', mode: fit}
+    waveform: {width: 800, height: 200, x: '0', y: '0', style: classic_line}
+    show:
+      artist: Edición especial
+";
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        let show = config.show_for(config.template("default").unwrap());
+        assert_eq!(show.title.as_deref(), Some("Nuevo Episodio"));
+        assert_eq!(show.artist.as_deref(), Some("Edición especial"));
+    }
+
+    #[tokio::test]
+    async fn test_init_writes_default_config() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("nested").join("config.yml");
+
+        let written = Config::init(Some(file_path.clone()), false).unwrap();
+        assert_eq!(written, file_path);
+        assert!(matches!(
+            Config::init(Some(file_path.clone()), false),
+            Err(AudiowaveError::ConfigExists(_))
+        ));
+        assert!(Config::init(Some(file_path.clone()), true).is_ok());
+
+        let config = Config::load(Some(file_path.to_str().unwrap().to_string()))
+            .await
+            .unwrap();
+        assert!(config.templates.contains_key("default"));
+    }
+
+    #[test]
+    fn test_default_title_does_not_overlap_subtitle() {
+        let config: Config = serde_yaml::from_str(DEFAULT_YAML).unwrap();
+        let mut template = config.template("default").unwrap().clone();
+        let _ = template.resolve_fonts();
+        let fields = Fields::from([
+            (
+                "title".to_string(),
+                "Episodio 142: una conversación muy larga sobre Rust, WebAssembly y el futuro de la web".to_string(),
+            ),
+            ("subtitle".to_string(), "Mi podcast".to_string()),
+        ]);
+        let filter = template.build_filter_complex(&fields, None, 0.0);
+        let draws: Vec<&str> = filter.split("drawtext=").skip(1).collect();
+        assert_eq!(draws.len(), 2, "el título debe ocupar una sola línea");
+
+        let size: u32 = draws[0].split(":fontsize=").nth(1).unwrap().split(':').next().unwrap().parse().unwrap();
+        let title = template.title.as_ref().unwrap();
+        let subtitle = template.subtitle.as_ref().unwrap();
+        let bottom = title.y.parse::<f64>().unwrap() + title.metrics().line_height(size);
+        assert!(bottom <= subtitle.y.parse::<f64>().unwrap(), "el título acaba en {bottom}");
     }
 
     #[tokio::test]
     async fn test_load_not_found() {
         let result = Config::load(Some("non_existent_file.yml".to_string())).await;
-        assert!(result.is_err());
+        assert!(matches!(result, Err(AudiowaveError::ConfigNotFound(_))));
     }
 }
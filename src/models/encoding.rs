@@ -0,0 +1,220 @@
+use crate::format::OutputFormat;
+use serde::Deserialize;
+
+/// Frame rate de salida si ni la plantilla ni la línea de comandos indican otro.
+pub const DEFAULT_FPS: u32 = 30;
+
+/// Familias de códecs que comparten opciones de velocidad y calidad.
+enum CodecFamily {
+    X26x,
+    Vpx,
+    Other,
+}
+
+impl CodecFamily {
+    fn of(codec: &str) -> Self {
+        match codec {
+            "libx264" | "libx265" => Self::X26x,
+            "libvpx" | "libvpx-vp9" => Self::Vpx,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Perfil de codificación de una plantilla. Los campos vacíos usan los valores por defecto.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct EncodingSettings {
+    pub fps: Option<u32>,
+    pub video_codec: Option<String>,
+    pub preset: Option<String>,
+    /// Calidad constante. No se puede combinar con `video_bitrate`.
+    pub crf: Option<u32>,
+    /// Bitrate de video (ej. "6M").
+    pub video_bitrate: Option<String>,
+    pub pix_fmt: Option<String>,
+    pub audio_codec: Option<String>,
+    /// Bitrate de audio (ej. "192k").
+    pub audio_bitrate: Option<String>,
+    /// Argumentos que se añaden tal cual antes del archivo de salida.
+    pub extra_args: Vec<String>,
+}
+
+impl EncodingSettings {
+    /// Argumentos de codificación para `format`. En modo borrador se fuerza la
+    /// configuración más rápida del códec.
+    pub fn to_args(&self, format: OutputFormat, draft: bool) -> Vec<String> {
+        let codec = self
+            .video_codec
+            .as_deref()
+            .unwrap_or(format.default_video_codec());
+        let mut args = vec!["-c:v".to_string(), codec.to_string()];
+
+        let push = |args: &mut Vec<String>, key: &str, value: String| {
+            args.push(key.to_string());
+            args.push(value);
+        };
+
+        match CodecFamily::of(codec) {
+            CodecFamily::X26x if draft => {
+                push(&mut args, "-preset", "ultrafast".to_string());
+                push(&mut args, "-crf", "28".to_string());
+            }
+            CodecFamily::X26x => {
+                push(&mut args, "-preset", self.preset.clone().unwrap_or_else(|| "slow".to_string()));
+                match (&self.video_bitrate, self.crf) {
+                    (Some(bitrate), None) => push(&mut args, "-b:v", bitrate.clone()),
+                    (_, crf) => push(&mut args, "-crf", crf.unwrap_or(18).to_string()),
+                }
+            }
+            // VP8/VP9 no tienen presets: la velocidad se controla con deadline/cpu-used
+            CodecFamily::Vpx if draft => {
+                push(&mut args, "-deadline", "realtime".to_string());
+                push(&mut args, "-cpu-used", "8".to_string());
+                push(&mut args, "-crf", "40".to_string());
+                push(&mut args, "-b:v", "0".to_string());
+            }
+            CodecFamily::Vpx => match (&self.video_bitrate, self.crf) {
+                (Some(bitrate), None) => push(&mut args, "-b:v", bitrate.clone()),
+                (_, crf) => {
+                    push(&mut args, "-crf", crf.unwrap_or(31).to_string());
+                    push(&mut args, "-b:v", "0".to_string());
+                }
+            },
+            CodecFamily::Other => {
+                if let Some(preset) = &self.preset {
+                    push(&mut args, "-preset", preset.clone());
+                }
+                if let Some(crf) = self.crf {
+                    push(&mut args, "-crf", crf.to_string());
+                }
+                if let Some(bitrate) = &self.video_bitrate {
+                    push(&mut args, "-b:v", bitrate.clone());
+                }
+            }
+        }
+
+        // GIF y WebP eligen su propio formato de píxel
+        let pix_fmt = self
+            .pix_fmt
+            .clone()
+            .or_else(|| format.has_audio().then(|| "yuv420p".to_string()));
+        if let Some(pix_fmt) = pix_fmt {
+            push(&mut args, "-pix_fmt", pix_fmt);
+        }
+
+        args.extend(self.audio_args(format));
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+
+    /// Argumentos de la pista de audio; vacío si el formato no lleva audio.
+    pub fn audio_args(&self, format: OutputFormat) -> Vec<String> {
+        let mut args = Vec::new();
+        if !format.has_audio() {
+            return args;
+        }
+        if let Some(codec) = self.audio_codec.as_deref().or(format.default_audio_codec()) {
+            args.extend(["-c:a".to_string(), codec.to_string()]);
+        }
+        if let Some(bitrate) = &self.audio_bitrate {
+            args.extend(["-b:a".to_string(), bitrate.clone()]);
+        }
+        args
+    }
+
+    /// Problemas de configuración del perfil.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.crf.is_some() && self.video_bitrate.is_some() {
+            problems.push("encoding: usa crf o video_bitrate, no los dos".to_string());
+        }
+        if self.fps == Some(0) {
+            problems.push("encoding: fps debe ser mayor que 0".to_string());
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_args() {
+        let args = EncodingSettings::default().to_args(OutputFormat::Mp4, false);
+        assert_eq!(
+            args,
+            ["-c:v", "libx264", "-preset", "slow", "-crf", "18", "-pix_fmt", "yuv420p", "-c:a", "aac"]
+        );
+    }
+
+    #[test]
+    fn test_webm_defaults() {
+        let args = EncodingSettings::default().to_args(OutputFormat::Webm, false);
+        assert_eq!(
+            args,
+            ["-c:v", "libvpx-vp9", "-crf", "31", "-b:v", "0", "-pix_fmt", "yuv420p", "-c:a", "libopus"]
+        );
+        let draft = EncodingSettings::default().to_args(OutputFormat::Webm, true);
+        assert!(draft.windows(2).any(|w| w == ["-deadline", "realtime"]));
+    }
+
+    #[test]
+    fn test_gif_has_no_audio_or_pix_fmt() {
+        let settings = EncodingSettings {
+            audio_codec: Some("aac".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(settings.to_args(OutputFormat::Gif, false), ["-c:v", "gif"]);
+    }
+
+    #[test]
+    fn test_draft_args() {
+        let settings = EncodingSettings {
+            preset: Some("veryslow".to_string()),
+            crf: Some(16),
+            ..Default::default()
+        };
+        let args = settings.to_args(OutputFormat::Mkv, true);
+        assert_eq!(
+            args,
+            ["-c:v", "libx264", "-preset", "ultrafast", "-crf", "28", "-pix_fmt", "yuv420p", "-c:a", "aac"]
+        );
+    }
+
+    #[test]
+    fn test_full_profile_from_yaml() {
+        let yaml = "
+fps: 25
+video_codec: libx265
+preset: medium
+video_bitrate: 6M
+pix_fmt: yuv420p10le
+audio_codec: aac
+audio_bitrate: 192k
+extra_args: ['-tag:v', 'hvc1']
+";
+        let settings: EncodingSettings = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(settings.fps, Some(25));
+        assert!(settings.validate().is_empty());
+        assert_eq!(
+            settings.to_args(OutputFormat::Mkv, false),
+            [
+                "-c:v", "libx265", "-preset", "medium", "-b:v", "6M", "-pix_fmt", "yuv420p10le",
+                "-c:a", "aac", "-b:a", "192k", "-tag:v", "hvc1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_crf_and_bitrate() {
+        let settings = EncodingSettings {
+            crf: Some(20),
+            video_bitrate: Some("4M".to_string()),
+            fps: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(settings.validate().len(), 2);
+    }
+}
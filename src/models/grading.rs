@@ -0,0 +1,137 @@
+use crate::escape;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Retoque de color y efectos de imagen. Sirve para el fondo (`background`) y
+/// para el paso final sobre el video compuesto (`post`). Los campos que no se
+/// indican no añaden filtro, salvo los valores por defecto del fondo.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Grading {
+    /// De -1 a 1; 0 no cambia el brillo.
+    pub brightness: Option<f64>,
+    /// De 0 a 3; 1 no cambia el contraste.
+    pub contrast: Option<f64>,
+    /// De 0 a 3; 1 no cambia la saturación.
+    pub saturation: Option<f64>,
+    /// Desenfoque (`sigma` de `gblur`); 0 lo desactiva.
+    pub blur: Option<f64>,
+    /// Oscurecimiento de los bordes, de 0 a 1.
+    pub vignette: Option<f64>,
+    /// Tabla de color `.cube` (o cualquier formato de `lut3d`).
+    pub lut: Option<String>,
+    /// Grano de película, de 0 a 100.
+    pub grain: Option<u32>,
+}
+
+/// Brillo y saturación del fondo cuando no se indican: lo oscurecen un poco para
+/// que la onda y los textos destaquen.
+pub const BACKGROUND_BRIGHTNESS: f64 = -0.1;
+pub const BACKGROUND_SATURATION: f64 = 0.95;
+
+impl Grading {
+    /// Cadena de filtros separada por comas; vacía si no hay nada que aplicar.
+    /// `brightness` y `saturation` son los valores cuando el campo no se indica.
+    pub fn to_filter(&self, brightness: f64, saturation: f64) -> String {
+        let mut filters = Vec::new();
+
+        let mut eq = Vec::new();
+        let brightness = self.brightness.unwrap_or(brightness);
+        if brightness != 0.0 {
+            eq.push(format!("brightness={brightness}"));
+        }
+        if let Some(contrast) = self.contrast.filter(|&c| c != 1.0) {
+            eq.push(format!("contrast={contrast}"));
+        }
+        let saturation = self.saturation.unwrap_or(saturation);
+        if saturation != 1.0 {
+            eq.push(format!("saturation={saturation}"));
+        }
+        if !eq.is_empty() {
+            filters.push(format!("eq={}", eq.join(":")));
+        }
+
+        if let Some(lut) = &self.lut {
+            filters.push(format!("lut3d=file={}", escape::filter_arg(lut)));
+        }
+        if let Some(blur) = self.blur.filter(|&b| b > 0.0) {
+            filters.push(format!("gblur=sigma={blur}"));
+        }
+        if let Some(vignette) = self.vignette.filter(|&v| v > 0.0) {
+            // `angle` va de 0 a PI/2: cuanto mayor, más oscuros los bordes
+            filters.push(format!("vignette=angle={:.4}", vignette * std::f64::consts::FRAC_PI_2));
+        }
+        if let Some(grain) = self.grain.filter(|&g| g > 0) {
+            filters.push(format!("noise=alls={grain}:allf=t+u"));
+        }
+        filters.join(",")
+    }
+
+    pub fn validate(&self, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        let ranges = [
+            ("brightness", self.brightness, -1.0, 1.0),
+            ("contrast", self.contrast, 0.0, 3.0),
+            ("saturation", self.saturation, 0.0, 3.0),
+            ("vignette", self.vignette, 0.0, 1.0),
+            ("blur", self.blur, 0.0, 1024.0),
+        ];
+        for (field, value, min, max) in ranges {
+            if let Some(value) = value
+                && !(min..=max).contains(&value)
+            {
+                problems.push(format!("{name}: {field} ({value}) debe estar entre {min} y {max}"));
+            }
+        }
+        if let Some(grain) = self.grain
+            && grain > 100
+        {
+            problems.push(format!("{name}: grain ({grain}) debe estar entre 0 y 100"));
+        }
+        if let Some(lut) = &self.lut
+            && !Path::new(lut).exists()
+        {
+            problems.push(format!("{name}: no existe la LUT '{lut}'"));
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_background_defaults() {
+        assert_eq!(
+            Grading::default().to_filter(BACKGROUND_BRIGHTNESS, BACKGROUND_SATURATION),
+            "eq=brightness=-0.1:saturation=0.95"
+        );
+        let undimmed = Grading { brightness: Some(0.0), saturation: Some(1.0), ..Default::default() };
+        assert_eq!(undimmed.to_filter(BACKGROUND_BRIGHTNESS, BACKGROUND_SATURATION), "");
+    }
+
+    #[test]
+    fn test_full_chain() {
+        let grading: Grading = serde_yaml::from_str(
+            "contrast: 1.1\nblur: 4\nvignette: 0.5\nlut: 'luts/teal, orange.cube'\ngrain: 12",
+        )
+        .unwrap();
+        assert_eq!(
+            grading.to_filter(0.0, 1.0),
+            "eq=contrast=1.1,lut3d=file='luts/teal, orange.cube',gblur=sigma=4,vignette=angle=0.7854,noise=alls=12:allf=t+u"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let grading = Grading {
+            brightness: Some(2.0),
+            vignette: Some(-0.5),
+            grain: Some(150),
+            lut: Some("no-existe.cube".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(grading.validate("post").len(), 4);
+    }
+}
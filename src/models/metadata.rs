@@ -1,66 +1,448 @@
+use crate::error::{AudiowaveError, Result};
+use crate::placeholder::Fields;
 use id3::{Tag, TagLike};
-use std::path::PathBuf;
+use log::debug;
+use serde::Deserialize;
+use std::{collections::HashMap, fs::File, path::Path, process::Command, sync::Arc};
+use tempfile::TempPath;
 use tokio::task;
 
+/// Lee las etiquetas de los formatos que no son MP3 (FLAC, Ogg, Opus, M4A, WAV...).
+const FFPROBE: &str = "ffprobe";
+/// Extrae la carátula incrustada cuando no viene en una etiqueta ID3.
+const FFMPEG: &str = "ffmpeg";
+
+/// Etiquetas leídas del audio. Los campos que el archivo no trae quedan en `None`
+/// para que se completen con los valores de [`crate::models::show::ShowSettings`].
+#[derive(Debug, Clone, Default)]
 pub struct AudioMetadata {
-    pub title: String,
-    pub artist: String,
-    pub cover_path: Option<PathBuf>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Número de pista o de episodio.
+    pub track: Option<u32>,
+    /// Fecha de grabación tal como viene en la etiqueta (ej. "2026-10-18" o "2026").
+    pub date: Option<String>,
+    pub comment: Option<String>,
+    pub genre: Option<String>,
+    pub composer: Option<String>,
+    /// Duración exacta en segundos.
+    pub duration: Option<f64>,
+    /// Carátula guardada en un archivo temporal. Se borra al soltar el último clon,
+    /// así que hay que pasarla al trabajo con [`crate::RenderJobBuilder::cover`].
+    pub cover: Option<Arc<TempPath>>,
 }
 
 impl AudioMetadata {
-    pub async fn new(input_path: String) -> Self {
-        task::spawn_blocking(move || {
-            let mut meta = AudioMetadata {
-                title: "Nuevo Episodio".to_string(),
-                artist: "atareao con Linux".to_string(),
-                cover_path: None,
-            };
+    /// Nombres de los campos que se pueden usar como `{campo}` en textos y nombres de archivo.
+    pub const FIELDS: &[&str] = &[
+        "title", "artist", "album", "episode", "track", "date", "year", "comment", "genre",
+        "composer", "duration",
+    ];
 
+    /// Lee las etiquetas y la carátula. ID3 tiene prioridad; ffprobe completa lo que
+    /// falte (o todo, en formatos que no son MP3) y aporta la duración exacta.
+    pub async fn new(input_path: String) -> Result<Self> {
+        task::spawn_blocking(move || {
+            let mut meta = AudioMetadata::default();
             if let Ok(tag) = Tag::read_from_path(&input_path) {
-                if let Some(t) = tag.title() {
-                    meta.title = t.to_string();
-                }
-                if let Some(a) = tag.artist() {
-                    meta.artist = a.to_string();
-                }
-
-                // Extraer carátula si existe (APIC tag)
-                if let Some(pic) = tag.pictures().next() {
-                    // Creamos un archivo temporal para la imagen
-                    if let Ok(temp) = tempfile::Builder::new()
-                        .prefix("audiowave_cover_")
-                        .suffix(".jpg")
-                        .tempfile()
-                    {
-                        let (mut file, path) =
-                            temp.keep().expect("Error al guardar imagen temporal");
-                        if std::io::Write::write_all(&mut file, &pic.data).is_ok() {
-                            meta.cover_path = Some(path);
-                        }
-                    }
-                }
+                meta.read_id3(&tag)?;
+            }
+            if let Some(probe) = Probe::run(&input_path) {
+                meta.read_probe(&input_path, &probe)?;
             }
-            meta
+            Ok(meta)
         })
         .await
-        .unwrap_or_else(|_| AudioMetadata {
-            title: "Error".to_string(),
-            artist: "Metadata".to_string(),
-            cover_path: None,
+        .map_err(|e| AudiowaveError::Metadata(e.to_string()))?
+    }
+
+    fn read_id3(&mut self, tag: &Tag) -> Result<()> {
+        self.title = non_empty(tag.title());
+        self.artist = non_empty(tag.artist());
+        self.album = non_empty(tag.album());
+        self.track = tag.track();
+        self.date = tag
+            .date_recorded()
+            .map(|d| d.to_string())
+            .or_else(|| tag.year().map(|y| y.to_string()));
+        // COMM sin descripción es el comentario principal
+        self.comment = tag
+            .comments()
+            .find(|c| c.description.is_empty())
+            .or_else(|| tag.comments().next())
+            .and_then(|c| non_empty(Some(&c.text)));
+        self.genre = non_empty(tag.genre_parsed().as_deref());
+        self.composer = non_empty(tag.get("TCOM").and_then(|f| f.content().text()));
+        // TLEN (milisegundos) solo se usa si ffprobe no está disponible
+        self.duration = tag.duration().map(|ms| f64::from(ms) / 1000.0);
+
+        // Extraer carátula si existe (APIC tag)
+        if let Some(pic) = tag.pictures().next() {
+            let (mut file, path) = temp_cover(cover_extension(&pic.mime_type))?;
+            if std::io::Write::write_all(&mut file, &pic.data).is_ok() {
+                self.cover = Some(Arc::new(path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rellena los campos vacíos con las etiquetas de ffprobe.
+    fn read_probe(&mut self, input_path: &str, probe: &Probe) -> Result<()> {
+        let tag = |key: &str| probe.tag(key).map(str::to_string);
+        self.title = self.title.take().or_else(|| tag("title"));
+        self.artist = self
+            .artist
+            .take()
+            .or_else(|| tag("artist"))
+            .or_else(|| tag("album_artist"));
+        self.album = self.album.take().or_else(|| tag("album"));
+        // MP4 guarda el número de episodio en su propio átomo
+        self.track = self.track.or_else(|| {
+            ["episode_sort", "track"]
+                .into_iter()
+                .find_map(|key| probe.tag(key).and_then(parse_number))
+        });
+        self.date = self.date.take().or_else(|| tag("date"));
+        self.comment = self
+            .comment
+            .take()
+            .or_else(|| tag("comment"))
+            .or_else(|| tag("description"));
+        self.genre = self.genre.take().or_else(|| tag("genre"));
+        self.composer = self.composer.take().or_else(|| tag("composer"));
+        if let Some(duration) = probe.duration() {
+            self.duration = Some(duration);
+        }
+        if self.cover.is_none()
+            && let Some(cover) = probe.cover_stream()
+        {
+            self.cover = extract_cover(input_path, cover)?.map(Arc::new);
+        }
+        Ok(())
+    }
+
+    /// Ruta de la carátula, si el audio trae una.
+    pub fn cover_path(&self) -> Option<&Path> {
+        self.cover.as_deref().map(|path| path.as_ref())
+    }
+
+    /// Valores de [`Self::FIELDS`]. Los que faltan quedan como cadena vacía.
+    pub fn fields(&self) -> Fields {
+        let year = self
+            .date
+            .as_deref()
+            .and_then(|d| d.get(..4))
+            .filter(|y| y.chars().all(|c| c.is_ascii_digit()));
+        let track = self.track.map(|t| t.to_string());
+        let values = [
+            ("title", self.title.clone()),
+            ("artist", self.artist.clone()),
+            ("album", self.album.clone()),
+            ("episode", track.clone()),
+            ("track", track),
+            ("date", self.date.clone()),
+            ("year", year.map(str::to_string)),
+            ("comment", self.comment.clone()),
+            ("genre", self.genre.clone()),
+            ("composer", self.composer.clone()),
+            ("duration", self.duration.map(format_duration)),
+        ];
+        values
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.unwrap_or_default()))
+            .collect()
+    }
+}
+
+/// "3/12" → 3.
+fn parse_number(value: &str) -> Option<u32> {
+    value.split('/').next()?.trim().parse().ok()
+}
+
+/// Segundos como "MM:SS", o "H:MM:SS" a partir de una hora.
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    let (h, m, s) = (total / 3600, total / 60 % 60, total % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m:02}:{s:02}")
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Salida de `ffprobe -show_format -show_streams` en JSON.
+#[derive(Debug, Default, Deserialize)]
+struct Probe {
+    #[serde(default)]
+    format: ProbeFormat,
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProbeFormat {
+    /// ffprobe escribe los números como cadenas.
+    duration: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProbeStream {
+    index: usize,
+    #[serde(default)]
+    codec_type: String,
+    #[serde(default)]
+    codec_name: String,
+    #[serde(default)]
+    disposition: HashMap<String, i64>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+impl Probe {
+    /// Ejecuta ffprobe. Devuelve `None` si no está instalado o no reconoce el archivo.
+    fn run(input_path: &str) -> Option<Self> {
+        let output = Command::new(FFPROBE)
+            .args(["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"])
+            .arg(input_path)
+            .output()
+            .inspect_err(|e| debug!("No se pudo ejecutar {FFPROBE}: {e}"))
+            .ok()?;
+        if !output.status.success() {
+            debug!("{FFPROBE} no reconoce {input_path}");
+            return None;
+        }
+        Self::parse(&output.stdout)
+    }
+
+    fn parse(json: &[u8]) -> Option<Self> {
+        serde_json::from_slice(json)
+            .inspect_err(|e| debug!("Salida de {FFPROBE} no válida: {e}"))
+            .ok()
+    }
+
+    /// Etiqueta del contenedor o, si no está, de la pista de audio (Ogg y Opus guardan
+    /// los comentarios Vorbis en la pista). Las claves no distinguen mayúsculas.
+    fn tag(&self, key: &str) -> Option<&str> {
+        let audio_tags = self
+            .streams
+            .iter()
+            .filter(|s| s.codec_type == "audio")
+            .map(|s| &s.tags);
+        std::iter::once(&self.format.tags)
+            .chain(audio_tags)
+            .flat_map(|tags| tags.iter())
+            .find(|(k, v)| k.eq_ignore_ascii_case(key) && !v.trim().is_empty())
+            .map(|(_, v)| v.trim())
+    }
+
+    fn duration(&self) -> Option<f64> {
+        self.format
+            .duration
+            .as_deref()
+            .and_then(|d| d.parse().ok())
+            .filter(|d: &f64| d.is_finite() && *d > 0.0)
+    }
+
+    /// Pista de video marcada como carátula adjunta.
+    fn cover_stream(&self) -> Option<&ProbeStream> {
+        self.streams.iter().find(|s| {
+            s.codec_type == "video" && s.disposition.get("attached_pic").copied() == Some(1)
         })
     }
 }
 
+/// Crea el archivo temporal de la carátula; se borra cuando se suelta la ruta.
+fn temp_cover(extension: &str) -> Result<(File, TempPath)> {
+    tempfile::Builder::new()
+        .prefix("audiowave_cover_")
+        .suffix(&format!(".{extension}"))
+        .tempfile()
+        .map(|temp| temp.into_parts())
+        .map_err(|e| AudiowaveError::Metadata(format!("Error al guardar imagen temporal: {e}")))
+}
+
+/// Extensión de la carátula según el tipo MIME de APIC ("image/png" o, en ID3v2.2,
+/// solo "PNG"). Lo que no se reconoce se guarda como JPEG.
+fn cover_extension(mime_type: &str) -> &'static str {
+    let subtype = mime_type.rsplit('/').next().unwrap_or_default();
+    match subtype.trim().to_ascii_lowercase().as_str() {
+        "png" => "png",
+        "gif" => "gif",
+        "bmp" => "bmp",
+        "webp" => "webp",
+        _ => "jpg",
+    }
+}
+
+/// Copia la carátula adjunta sin recodificar. Si FFmpeg falla se sigue sin carátula.
+fn extract_cover(input_path: &str, stream: &ProbeStream) -> Result<Option<TempPath>> {
+    let extension = if stream.codec_name == "png" { "png" } else { "jpg" };
+    let (_, path) = temp_cover(extension)?;
+    let status = Command::new(FFMPEG)
+        .args(["-v", "error", "-y", "-i", input_path, "-map"])
+        .arg(format!("0:{}", stream.index))
+        .args(["-c", "copy", "-frames:v", "1", "-update", "1"])
+        .arg(&path)
+        .status();
+    match status {
+        Ok(status) if status.success() => Ok(Some(path)),
+        other => {
+            debug!("No se pudo extraer la carátula de {input_path}: {other:?}");
+            Ok(None)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[tokio::test]
     async fn test_new_with_non_existent_file() {
-        let metadata = AudioMetadata::new("non_existent_file.mp3".to_string()).await;
-        assert_eq!(metadata.title, "Nuevo Episodio");
-        assert_eq!(metadata.artist, "atareao con Linux");
-        assert!(metadata.cover_path.is_none());
+        let metadata = AudioMetadata::new("non_existent_file.mp3".to_string())
+            .await
+            .unwrap();
+        assert!(metadata.title.is_none());
+        assert!(metadata.artist.is_none());
+        assert!(metadata.album.is_none());
+        assert!(metadata.cover.is_none());
+    }
+
+    #[test]
+    fn test_probe_fills_missing_fields() {
+        let json = br#"{
+            "streams": [{"index": 0, "codec_name": "aac", "codec_type": "audio"}],
+            "format": {"duration": "3725.480000", "tags": {
+                "title": "De otra fuente", "track": "123/200", "date": "2026-10-18",
+                "comment": "Notas del episodio", "genre": "Podcast", "composer": "Ana"
+            }}
+        }"#;
+        let probe = Probe::parse(json).unwrap();
+        let mut meta = AudioMetadata {
+            title: Some("Desde ID3".to_string()),
+            ..Default::default()
+        };
+        meta.read_probe("in.m4a", &probe).unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Desde ID3"));
+        assert_eq!(meta.track, Some(123));
+        assert_eq!(meta.duration, Some(3725.48));
+
+        let fields = meta.fields();
+        assert_eq!(fields["episode"], "123");
+        assert_eq!(fields["year"], "2026");
+        assert_eq!(fields["duration"], "1:02:05");
+        assert_eq!(fields["album"], "");
+        assert_eq!(fields.len(), AudioMetadata::FIELDS.len());
+    }
+
+    #[test]
+    fn test_read_id3_frames() {
+        use id3::{Version, frame::Comment};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("episodio.mp3");
+        File::create(&path).unwrap();
+        let mut tag = Tag::new();
+        tag.set_title("Episodio");
+        tag.set_album("Temporada 3");
+        tag.set_track(123);
+        tag.set_date_recorded("2026-10-18".parse().unwrap());
+        tag.set_genre("Podcast");
+        tag.set_text("TCOM", "Ana");
+        tag.add_frame(Comment {
+            lang: "spa".to_string(),
+            description: String::new(),
+            text: "Notas".to_string(),
+        });
+        tag.write_to_path(&path, Version::Id3v24).unwrap();
+
+        let mut meta = AudioMetadata::default();
+        meta.read_id3(&Tag::read_from_path(&path).unwrap()).unwrap();
+        assert_eq!(meta.album.as_deref(), Some("Temporada 3"));
+        assert_eq!(meta.track, Some(123));
+        assert_eq!(meta.date.as_deref(), Some("2026-10-18"));
+        assert_eq!(meta.comment.as_deref(), Some("Notas"));
+        assert_eq!(meta.genre.as_deref(), Some("Podcast"));
+        assert_eq!(meta.composer.as_deref(), Some("Ana"));
+        assert!(meta.cover.is_none());
+    }
+
+    #[test]
+    fn test_id3_cover_is_temporary_and_keeps_its_type() {
+        use id3::frame::{Picture, PictureType};
+
+        let mut tag = Tag::new();
+        tag.add_frame(Picture {
+            mime_type: "image/png".to_string(),
+            picture_type: PictureType::CoverFront,
+            description: String::new(),
+            data: vec![0x89, b'P', b'N', b'G'],
+        });
+        let mut meta = AudioMetadata::default();
+        meta.read_id3(&tag).unwrap();
+        let path = meta.cover_path().unwrap().to_path_buf();
+        assert_eq!(path.extension().unwrap(), "png");
+        assert!(path.exists());
+
+        // Un clon (el del trabajo) mantiene el archivo; el último lo borra
+        let cover = meta.cover.clone();
+        drop(meta);
+        assert!(path.exists());
+        drop(cover);
+        assert!(!path.exists());
+
+        assert_eq!(cover_extension("PNG"), "png");
+        assert_eq!(cover_extension("image/jpeg"), "jpg");
+        assert_eq!(cover_extension("-->"), "jpg");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(65.4), "01:05");
+        assert_eq!(format_duration(3600.0), "1:00:00");
+    }
+
+    #[test]
+    fn test_probe_flac_tags_and_cover() {
+        let json = br#"{
+            "streams": [
+                {"index": 0, "codec_name": "flac", "codec_type": "audio", "disposition": {"attached_pic": 0}},
+                {"index": 1, "codec_name": "png", "codec_type": "video", "disposition": {"attached_pic": 1},
+                 "tags": {"comment": "Cover (front)"}}
+            ],
+            "format": {"format_name": "flac", "tags": {"TITLE": "Episodio 42", "ARTIST": "Podcast", "ALBUM": ""}}
+        }"#;
+        let probe = Probe::parse(json).unwrap();
+        assert_eq!(probe.tag("title"), Some("Episodio 42"));
+        assert_eq!(probe.tag("artist"), Some("Podcast"));
+        assert_eq!(probe.tag("album"), None);
+        let cover = probe.cover_stream().unwrap();
+        assert_eq!((cover.index, cover.codec_name.as_str()), (1, "png"));
+    }
+
+    #[test]
+    fn test_probe_opus_stream_tags() {
+        let json = br#"{
+            "streams": [
+                {"index": 0, "codec_name": "opus", "codec_type": "audio", "tags": {"title": "Ogg title", "album_artist": "Show"}}
+            ],
+            "format": {"format_name": "ogg", "tags": {"encoder": "Lavf"}}
+        }"#;
+        let probe = Probe::parse(json).unwrap();
+        assert_eq!(probe.tag("TITLE"), Some("Ogg title"));
+        assert_eq!(probe.tag("artist"), None);
+        assert_eq!(probe.tag("album_artist"), Some("Show"));
+        assert!(probe.cover_stream().is_none());
+        assert!(Probe::parse(b"not json").is_none());
     }
 }
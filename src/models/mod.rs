@@ -1,4 +1,5 @@
 pub mod video;
+pub mod encoding;
 pub mod background;
 pub mod waveform;
 pub mod text;
@@ -6,6 +7,9 @@ pub mod template;
 pub mod config;
 pub mod style;
 pub mod metadata;
+pub mod show;
+pub mod animation;
+pub mod grading;
 
 pub use config::Config;
 pub use metadata::AudioMetadata;
@@ -0,0 +1,84 @@
+use crate::placeholder::Fields;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Datos del programa que se usan cuando el audio no trae sus propias etiquetas.
+///
+/// Se puede definir en la raíz de la configuración (`show:` o `defaults:`) y
+/// sobreescribir por plantilla o desde la línea de comandos.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ShowSettings {
+    /// Título del episodio si el audio no tiene.
+    pub title: Option<String>,
+    /// Artista o autor; se usa como subtítulo.
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Imagen de fondo si el audio no tiene carátula.
+    pub background: Option<String>,
+    /// Campos propios para los textos (ej. `web: atareao.es` para usar `{web}`).
+    pub vars: Fields,
+}
+
+impl ShowSettings {
+    /// Combina dos niveles: los campos definidos en `over` ganan.
+    pub fn merge(&self, over: &ShowSettings) -> ShowSettings {
+        ShowSettings {
+            title: over.title.clone().or_else(|| self.title.clone()),
+            artist: over.artist.clone().or_else(|| self.artist.clone()),
+            album: over.album.clone().or_else(|| self.album.clone()),
+            background: over.background.clone().or_else(|| self.background.clone()),
+            vars: self
+                .vars
+                .iter()
+                .chain(&over.vars)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        match &self.background {
+            Some(path) if !Path::new(path).exists() => {
+                vec![format!("show: no existe la imagen de fondo '{path}'")]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_prefers_override() {
+        let base = ShowSettings {
+            title: Some("Nuevo Episodio".to_string()),
+            artist: Some("Mi podcast".to_string()),
+            ..Default::default()
+        };
+        let over = ShowSettings {
+            artist: Some("Otro podcast".to_string()),
+            album: Some("Temporada 2".to_string()),
+            vars: Fields::from([("web".to_string(), "atareao.es".to_string())]),
+            ..Default::default()
+        };
+        let merged = base.merge(&over);
+        assert_eq!(merged.title.as_deref(), Some("Nuevo Episodio"));
+        assert_eq!(merged.artist.as_deref(), Some("Otro podcast"));
+        assert_eq!(merged.album.as_deref(), Some("Temporada 2"));
+        assert_eq!(merged.background, None);
+        assert_eq!(merged.vars["web"], "atareao.es");
+    }
+
+    #[test]
+    fn test_validate_missing_background() {
+        let show = ShowSettings {
+            background: Some("/no/existe/fondo.png".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(show.validate().len(), 1);
+        assert!(ShowSettings::default().validate().is_empty());
+    }
+}
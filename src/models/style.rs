@@ -1,104 +1,153 @@
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "snake_case")] // Permite escribir "classic_line" en el YAML
-pub enum WaveformStyle {
+/// Ficha descriptiva de un estilo: nombre en el YAML, descripción y color por defecto.
+pub struct StyleInfo {
+    pub style: WaveformStyle,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default_color: Option<&'static str>,
+}
+
+/// Declara `WaveformStyle` y `CATALOG` a la vez: el comentario de cada variante
+/// es también la descripción que muestra `audiowave styles`.
+macro_rules! styles {
+    ($( #[doc = $doc:literal] $variant:ident => $name:literal, $color:expr; )*) => {
+        #[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+        pub enum WaveformStyle {
+            // El nombre del YAML es el mismo del catálogo
+            $( #[doc = $doc] #[serde(rename = $name)] $variant, )*
+        }
+
+        /// Catálogo de todos los estilos disponibles, en el orden en que se declaran.
+        pub const CATALOG: &[StyleInfo] = &[
+            $(
+                StyleInfo {
+                    style: WaveformStyle::$variant,
+                    name: $name,
+                    description: $doc.trim_ascii(),
+                    default_color: $color,
+                },
+            )*
+        ];
+    };
+}
+
+styles! {
     /// Una línea clásica que representa la onda de sonido.
-    ClassicLine,
+    ClassicLine => "classic_line", Some("cyan");
     /// Un espectro de frecuencias con estética cyberpunk.
-    CyberpunkSpectrum,
+    CyberpunkSpectrum => "cyberpunk_spectrum", Some("magma");
     /// Simula la pantalla de un osciloscopio analógico.
-    AnalogOscilloscope,
+    AnalogOscilloscope => "analog_oscilloscope", None;
     /// Barras sólidas tipo ecualizador antiguo.
-    RetroStep,
+    RetroStep => "retro_step", Some("0x00FF00|0xFFFF00|0xFF0000");
     /// Histograma de audio tipo cascada.
-    NebulaHistogram,
+    NebulaHistogram => "nebula_histogram", Some("rainbow");
     /// Espectro de frecuencia circular.
-    PrismFrequency,
+    PrismFrequency => "prism_frequency", Some("magenta");
     /// Bloques de volumen dinámicos.
-    DigitalPulse,
+    DigitalPulse => "digital_pulse", Some("0xEE7611");
     /// Onda con reflejo inferior y brillo (glow).
-    NeonMirror,
+    NeonMirror => "neon_mirror", Some("cyan");
     /// Fondo translúcido con desenfoque gaussiano.
-    GlassBlur,
+    GlassBlur => "glass_blur", Some("white");
     /// Estilo minimalista con rastro de movimiento (transparencia dinámica).
-    GhostFrequency,
+    GhostFrequency => "ghost_frequency", Some("cyan");
     /// Onda circular tipo Iron Man / HUD.
-    CyberCircle,
+    CyberCircle => "cyber_circle", Some("0x00FFFF");
     /// Estilo fluido con deformación y color cálido.
-    LiquidGold,
+    LiquidGold => "liquid_gold", Some("0xFFD700");
     /// Rayos aleatorios basados en picos de frecuencia.
-    ElectricStorm,
+    ElectricStorm => "electric_storm", Some("0x8888FF");
     /// Acumulación de espectro en 3D falso (estilo montaña).
-    ZenithStack,
+    ZenithStack => "zenith_stack", Some("fire");
     /// Radar circular con barrido de frecuencia.
-    PulseRadar,
+    PulseRadar => "pulse_radar", Some("0x00FF00");
     /// Barras simétricas desde el centro (tipo Audiogram).
-    StudioBars,
+    StudioBars => "studio_bars", Some("white");
     /// Línea única ultra-fina con degradado suave.
-    MinimalMono,
+    MinimalMono => "minimal_mono", Some("white");
     /// Área rellena (silhouette) con suavizado.
-    WaveformSolid,
+    WaveformSolid => "waveform_solid", Some("white@0.5");
     /// Puntos que reaccionan sutilmente (estilo elegante).
-    BroadcastPoint,
+    BroadcastPoint => "broadcast_point", Some("white@0.6");
     /// Onda suave y contínua, ideal para la voz humana.
-    TalkFlow,
+    TalkFlow => "talk_flow", Some("white");
     /// Estilo clásico de redes sociales con puntas redondeadas.
-    AudiogramBars,
+    AudiogramBars => "audiogram_bars", Some("white");
     /// Una silueta rellena que parece una sombra proyectada.
-    VoiceShadow,
+    VoiceShadow => "voice_shadow", Some("white@0.3");
     /// Un círculo concéntrico sutil que rodea un elemento central.
-    SpectrumCircle,
+    SpectrumCircle => "spectrum_circle", Some("white");
     /// Una onda moderna con un efecto de brillo.
-    GlowWaveModern,
+    GlowWaveModern => "glow_wave_modern", Some("#00e5ff");
     /// Un ecualizador de 32 bandas.
-    Equalizer32Bands,
+    Equalizer32Bands => "equalizer32_bands", Some("#ffffff");
     /// Un ecualizador de 128 bandas.
-    Equalizer128Bands,
+    Equalizer128Bands => "equalizer128_bands", Some("magma");
     /// Un ecualizador de 10 bandas.
-    Equalizer10Bands,
+    Equalizer10Bands => "equalizer10_bands", Some("#ffffff");
     /// Una onda de sonido en forma circular.
-    CircularWave,
+    CircularWave => "circular_wave", Some("white");
     /// Una línea suave y continua, con un ligero desenfoque.
-    SmoothLine,
-    /// Tres capas de profundidad
-    ProfessionalNeon,
-    /// Efecto espejo con degradado
-    CyberReflex,
-    /// Espectrograma Circular Dinámico
-    MagmaVortex,
-    /// Partículas de espectro en Fuga
-    Interstellar,
-    /// Espejo Central
-    VaporWaveMirror,
-    /// Efecto Estela
-    CyberGhost,
-    ///Degradado vertical
-    ToxicPulse,
-    /// Circular con 128 bandas
-    CircularNebula
+    SmoothLine => "smooth_line", Some("white");
+    /// Tres capas de profundidad con brillo neón.
+    ProfessionalNeon => "professional_neon", Some("#00f2ff");
+    /// Efecto espejo con degradado.
+    CyberReflex => "cyber_reflex", Some("#00ff95");
+    /// Espectrograma circular dinámico.
+    MagmaVortex => "magma_vortex", Some("fire");
+    /// Partículas de espectro en fuga.
+    Interstellar => "interstellar", Some("aqua");
+    /// Espejo central con degradado vaporwave.
+    VaporWaveMirror => "vapor_wave_mirror", Some("white");
+    /// Efecto estela sobre barras de frecuencia.
+    CyberGhost => "cyber_ghost", Some("cyan");
+    /// Barras con degradado vertical.
+    ToxicPulse => "toxic_pulse", Some("cyan");
+    /// Espectro circular con 128 bandas.
+    CircularNebula => "circular_nebula", Some("cyan");
 }
 
 impl WaveformStyle {
+    /// Devuelve la ficha del estilo dentro del catálogo.
+    pub fn info(&self) -> &'static StyleInfo {
+        CATALOG
+            .iter()
+            .find(|info| info.style == *self)
+            .expect("Todos los estilos deben estar en el catálogo")
+    }
+
+    /// Nombre con el que se escribe el estilo en el YAML.
+    pub fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Color que se usa cuando la plantilla no define uno.
+    pub fn default_color(&self) -> Option<&'static str> {
+        self.info().default_color
+    }
+
     pub fn get_filter(
         &self,
         width: u32,
         height: u32,
         color: Option<&str>,
-        rate: Option<i32>,
+        rate: i32,
     ) -> String {
         let rgba_colorkey = ",format=rgba,colorkey=0x000000:0.1:0.1";
-        let actual_rate = rate.unwrap_or(60);
+        let color = color.or(self.default_color());
+        let actual_rate = rate;
         match self {
             Self::ClassicLine => {
-                let color = color.unwrap_or("cyan");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=line:colors={color}:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::CyberpunkSpectrum => {
-                let color = color.unwrap_or("magma");
+                let color = color.unwrap_or_default();
                 format!(
                     "showspectrum=s={width}x{height}:color={color}:rate={actual_rate}{rgba_colorkey}"
                 )
@@ -111,35 +160,35 @@ impl WaveformStyle {
             }
 
             Self::RetroStep => {
-                let colors = color.unwrap_or("0x00FF00|0xFFFF00|0xFF0000");
+                let colors = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={width}x{height}:mode=bar:colors={colors}:fscale=log:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::NebulaHistogram => {
-                let color = color.unwrap_or("rainbow");
+                let color = color.unwrap_or_default();
                 format!(
                     "ahistogram=s={width}x{height}:color={color}:scale=log:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::PrismFrequency => {
-                let color = color.unwrap_or("magenta");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=p2p:colors={color}:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::DigitalPulse => {
-                let color = color.unwrap_or("0xEE7611");
+                let color = color.unwrap_or_default();
                 format!(
                     "showvolume=w={width}:h={height}:f=0.9:c={color}:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::NeonMirror => {
-                let color = color.unwrap_or("cyan");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={w}x{h}:mode=line:colors={color}:rate={actual_rate},format=rgba[wave_raw]; \
                      [wave_raw]split[fg][bg_glow]; \
@@ -151,7 +200,7 @@ impl WaveformStyle {
             }
 
             Self::GlassBlur => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={w}x{h}:mode=bar:colors={color}:rate={actual_rate},format=rgba[wave]; \
                      [wave]drawbox=t=fill:color=black@0.4,boxblur=luma_radius=10:luma_power=1[glass]; \
@@ -162,14 +211,14 @@ impl WaveformStyle {
             }
 
             Self::GhostFrequency => {
-                let color = color.unwrap_or("cyan");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=p2p:colors={color}:rate={actual_rate},lagfun=decay=0.95{rgba_colorkey}"
                 )
             }
 
             Self::CyberCircle => {
-                let color = color.unwrap_or("0x00FFFF");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={w}x{h}:mode=line:colors={color}:rate={actual_rate},format=rgba, \
                      polar=r=min(w\\,h)/2,colorkey=0x000000:0.1:0.1",
@@ -179,7 +228,7 @@ impl WaveformStyle {
             }
 
             Self::LiquidGold => {
-                let color = color.unwrap_or("0xFFD700");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={w}x{h}:mode=p2p:colors={color}:rate={actual_rate},format=rgba, \
                      boxblur=2:1,colorkey=0x000000:0.1:0.1",
@@ -189,7 +238,7 @@ impl WaveformStyle {
             }
 
             Self::ElectricStorm => {
-                let color = color.unwrap_or("0x8888FF");
+                let color = color.unwrap_or_default();
                 format!(
                     "showpeaks=s={w}x{h}:mode=line:color={color}:rate={actual_rate},format=rgba, \
                      boxblur=10:1,colorkey=0x000000:0.1:0.1",
@@ -199,7 +248,7 @@ impl WaveformStyle {
             }
 
             Self::ZenithStack => {
-                let color = color.unwrap_or("fire");
+                let color = color.unwrap_or_default();
                 format!(
                     "showspectrum=s={w}x{h}:mode=combined:color={color}:slide=scroll:fscale=log:rate={actual_rate},format=rgba, \
                      perspective=x0=0.2*W:y0=0:x1=0.8*W:y1=0:x2=0:y2=H:x3=W:y3=H,colorkey=0x000000:0.1:0.1",
@@ -209,7 +258,7 @@ impl WaveformStyle {
             }
 
             Self::PulseRadar => {
-                let colors = color.unwrap_or("0x00FF00");
+                let colors = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={w}x{h}:mode=bar:colors={colors}:rate={actual_rate},format=rgba, \
                      polar=r=min(w\\,h)/2,lagfun=decay=0.9,colorkey=0x000000:0.1:0.1",
@@ -219,56 +268,56 @@ impl WaveformStyle {
             }
 
             Self::StudioBars => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=cline:colors={color}:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::MinimalMono => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=line:colors={color}:draw=full:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::WaveformSolid => {
-                let color = color.unwrap_or("white@0.5");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=p2p:colors={color}:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::BroadcastPoint => {
-                let color = color.unwrap_or("white@0.6");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=point:colors={color}:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::TalkFlow => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=p2p:colors={color}:draw=full:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::AudiogramBars => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={width}x{height}:mode=bar:colors={color}:fscale=log:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::VoiceShadow => {
-                let color = color.unwrap_or("white@0.3");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=cline:colors={color}:draw=full:rate={actual_rate}{rgba_colorkey}"
                 )
             }
 
             Self::SpectrumCircle => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showspectrum=s={w}x{h}:mode=combined:color={color}:slide=scroll:overlap=0.9:rate={actual_rate},format=rgba, \
                      polar=r=min(w\\,h)/2,colorkey=0x000000:0.1:0.1",
@@ -278,7 +327,7 @@ impl WaveformStyle {
             }
 
             Self::GlowWaveModern => {
-                let color = color.unwrap_or("#00e5ff");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=cline:rate={actual_rate}:colors={color},format=rgba[wave_raw]; \
                      [wave_raw]format=rgba,split[wave1][wave2]; \
@@ -288,7 +337,7 @@ impl WaveformStyle {
             }
 
             Self::Equalizer32Bands => {
-                let color = color.unwrap_or("#ffffff");
+                let color = color.unwrap_or_default();
                 let freqs = [
                     20, 25, 31, 40, 50, 63, 80, 100, 125, 160, 200, 250, 315, 400, 500, 630, 800,
                     1000, 1250, 1500, 2000, 2500, 3150, 4000, 5000, 6300, 8000, 12000, 16000,
@@ -354,7 +403,7 @@ impl WaveformStyle {
             }
 
             Self::Equalizer128Bands => {
-                let color_input = color.unwrap_or("magma");
+                let color_input = color.unwrap_or_default();
 
                 format!(
                     "showfreqs=s={w}x{h}:mode=bar:fscale=log:ascale=log:colors={c}[mask]; \
@@ -376,7 +425,7 @@ impl WaveformStyle {
             }
 
             Self::Equalizer10Bands => {
-                let color_input = color.unwrap_or("#ffffff");
+                let color_input = color.unwrap_or_default();
                 let freqs = [31, 63, 125, 250, 500, 1000, 2000, 4000, 8000, 16000];
 
                 // Definimos el degradado para las 10 bandas (de graves a agudos)
@@ -450,7 +499,7 @@ impl WaveformStyle {
             }
 
             Self::CircularWave => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={w}x{h}:mode=cline:colors={c}:draw=full:rate={r},format=rgba,split[fill][border]; \
          [fill]colorchannelmixer=aa=0.3[fill_t]; \
@@ -466,14 +515,14 @@ impl WaveformStyle {
                 )
             }
             Self::SmoothLine => {
-                let color = color.unwrap_or("white");
+                let color = color.unwrap_or_default();
                 format!(
                     "showwaves=s={width}x{height}:mode=line:colors={color}:rate={actual_rate},format=rgba,colorkey=0x000000:0.1:0.1,boxblur=1"
                 )
             }
 
             Self::ProfessionalNeon => {
-                let color_main = color.unwrap_or("#00f2ff"); // Cyan neón
+                let color_main = color.unwrap_or_default();
                 let color_glow = "#0066ff"; // Azul eléctrico para el aura
 
                 format!(
@@ -494,7 +543,7 @@ impl WaveformStyle {
             }
 
             Self::CyberReflex => {
-                let c1 = color.unwrap_or("#00ff95"); // Verde neón
+                let c1 = color.unwrap_or_default();
                 format!(
                     "asplit=2[a1][a2]; \
          [a1]showwaves=s={w}x{h_half}:mode=cline:r={r}:colors={c}:draw=full,format=rgba[top]; \
@@ -508,7 +557,7 @@ impl WaveformStyle {
             }
 
             Self::MagmaVortex => {
-                let c = color.unwrap_or("fire"); // Mapa de color 'fire', 'magma' o 'viridis'
+                let c = color.unwrap_or_default();
                 format!(
                     "showspectrum=s={w}x{h}:color={c}:mode=combined:slide=scroll:fscale=log:rate={r},format=rgba, \
                      polar=r=min(w\\,h)/2,colorkey=0x000000:0.1:0.1",
@@ -520,7 +569,7 @@ impl WaveformStyle {
             }
 
             Self::Interstellar => {
-                let c = color.unwrap_or("aqua");
+                let c = color.unwrap_or_default();
                 format!(
                     "ahistogram=s={w}x{h}:color={c}:scale=log:rate={r},format=rgba, \
          perspective=x0=0.3*W:y0=0:x1=0.7*W:y1=0:x2=0:y2=H:x3=W:y3=H, \
@@ -533,7 +582,7 @@ impl WaveformStyle {
             }
 
             Self::VaporWaveMirror => {
-                let c = color.unwrap_or("white");
+                let c = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={w}x{h_half}:mode=bar:fscale=log:colors={c}[v]; \
          [v]scale=128:{h_half}:flags=neighbor,scale={w}:{h_half}:flags=neighbor,split[top][bot_pre]; \
@@ -554,7 +603,7 @@ impl WaveformStyle {
             }
 
             Self::CyberGhost => {
-                let c = color.unwrap_or("cyan");
+                let c = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={w}x{h}:mode=bar:fscale=log:colors={c}[mask]; \
          [mask]scale=128:{h}:flags=neighbor,scale={w}:{h}:flags=neighbor,format=rgba, \
@@ -574,7 +623,7 @@ impl WaveformStyle {
             }
 
             Self::ToxicPulse => {
-                let c = color.unwrap_or("cyan");
+                let c = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={w}x{h}:mode=bar:fscale=log:colors={c}[mask]; \
                      [mask]scale=128:{h}:flags=neighbor,scale={w}:{h}:flags=neighbor[v_mask]; \
@@ -592,7 +641,7 @@ impl WaveformStyle {
             }
 
             Self::CircularNebula => {
-                let c = color.unwrap_or("cyan");
+                let c = color.unwrap_or_default();
                 format!(
                     "showfreqs=s={w}x{h}:mode=bar:fscale=log:ascale=log:colors={c}[v]; \
                      [v]scale=128:{h}:flags=neighbor,scale={w}:{h}:flags=neighbor,format=rgba[mask]; \
@@ -622,7 +671,7 @@ mod tests {
     fn test_get_filter_classic_line() {
         let style = WaveformStyle::ClassicLine;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=line:colors=cyan:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -630,7 +679,7 @@ mod tests {
     fn test_get_filter_classic_line_with_color() {
         let style = WaveformStyle::ClassicLine;
         assert_eq!(
-            style.get_filter(100, 50, Some("red"), None),
+            style.get_filter(100, 50, Some("red"), 60),
             "showwaves=s=100x50:mode=line:colors=red:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -639,7 +688,7 @@ mod tests {
     fn test_get_filter_cyberpunk_spectrum() {
         let style = WaveformStyle::CyberpunkSpectrum;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showspectrum=s=100x50:color=magma:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -648,7 +697,7 @@ mod tests {
     fn test_get_filter_analog_oscilloscope() {
         let style = WaveformStyle::AnalogOscilloscope;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "avectorscope=s=100x50:zoom=1.5:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -657,7 +706,7 @@ mod tests {
     fn test_get_filter_retro_step() {
         let style = WaveformStyle::RetroStep;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showfreqs=s=100x50:mode=bar:colors=0x00FF00|0xFFFF00|0xFF0000:fscale=log:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -666,7 +715,7 @@ mod tests {
     fn test_get_filter_nebula_histogram() {
         let style = WaveformStyle::NebulaHistogram;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "ahistogram=s=100x50:color=rainbow:scale=log:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -675,7 +724,7 @@ mod tests {
     fn test_get_filter_prism_frequency() {
         let style = WaveformStyle::PrismFrequency;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=p2p:colors=magenta:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -684,7 +733,7 @@ mod tests {
     fn test_get_filter_digital_pulse() {
         let style = WaveformStyle::DigitalPulse;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showvolume=w=100:h=50:f=0.9:c=0xEE7611:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -693,7 +742,7 @@ mod tests {
     fn test_get_filter_neon_mirror() {
         let style = WaveformStyle::NeonMirror;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=line:colors=cyan:rate=60,format=rgba[wave_raw]; [wave_raw]split[fg][bg_glow]; [bg_glow]boxblur=10:5[glow]; [glow][fg]overlay=format=auto,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -702,7 +751,7 @@ mod tests {
     fn test_get_filter_glass_blur() {
         let style = WaveformStyle::GlassBlur;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showfreqs=s=100x50:mode=bar:colors=white:rate=60,format=rgba[wave]; [wave]drawbox=t=fill:color=black@0.4,boxblur=luma_radius=10:luma_power=1[glass]; [glass][wave]overlay,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -711,7 +760,7 @@ mod tests {
     fn test_get_filter_ghost_frequency() {
         let style = WaveformStyle::GhostFrequency;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=p2p:colors=cyan:rate=60,lagfun=decay=0.95,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -720,7 +769,7 @@ mod tests {
     fn test_get_filter_cyber_circle() {
         let style = WaveformStyle::CyberCircle;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=line:colors=0x00FFFF:rate=60,format=rgba, polar=r=min(w\\,h)/2,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -729,7 +778,7 @@ mod tests {
     fn test_get_filter_liquid_gold() {
         let style = WaveformStyle::LiquidGold;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=p2p:colors=0xFFD700:rate=60,format=rgba, boxblur=2:1,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -738,7 +787,7 @@ mod tests {
     fn test_get_filter_electric_storm() {
         let style = WaveformStyle::ElectricStorm;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showpeaks=s=100x50:mode=line:color=0x8888FF:rate=60,format=rgba, boxblur=10:1,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -747,7 +796,7 @@ mod tests {
     fn test_get_filter_zenith_stack() {
         let style = WaveformStyle::ZenithStack;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showspectrum=s=100x50:mode=combined:color=fire:slide=scroll:fscale=log:rate=60,format=rgba, perspective=x0=0.2*W:y0=0:x1=0.8*W:y1=0:x2=0:y2=H:x3=W:y3=H,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -756,7 +805,7 @@ mod tests {
     fn test_get_filter_pulse_radar() {
         let style = WaveformStyle::PulseRadar;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showfreqs=s=100x50:mode=bar:colors=0x00FF00:rate=60,format=rgba, \
                      polar=r=min(w\\,h)/2,lagfun=decay=0.9,colorkey=0x000000:0.1:0.1"
         );
@@ -766,7 +815,7 @@ mod tests {
     fn test_get_filter_studio_bars() {
         let style = WaveformStyle::StudioBars;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=cline:colors=white:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -775,7 +824,7 @@ mod tests {
     fn test_get_filter_minimal_mono() {
         let style = WaveformStyle::MinimalMono;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=line:colors=white:draw=full:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -784,7 +833,7 @@ mod tests {
     fn test_get_filter_waveform_solid() {
         let style = WaveformStyle::WaveformSolid;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=p2p:colors=white@0.5:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -793,7 +842,7 @@ mod tests {
     fn test_get_filter_broadcast_point() {
         let style = WaveformStyle::BroadcastPoint;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=point:colors=white@0.6:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -802,7 +851,7 @@ mod tests {
     fn test_get_filter_talk_flow() {
         let style = WaveformStyle::TalkFlow;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=p2p:colors=white:draw=full:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -811,7 +860,7 @@ mod tests {
     fn test_get_filter_audiogram_bars() {
         let style = WaveformStyle::AudiogramBars;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showfreqs=s=100x50:mode=bar:colors=white:fscale=log:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -820,7 +869,7 @@ mod tests {
     fn test_get_filter_voice_shadow() {
         let style = WaveformStyle::VoiceShadow;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=cline:colors=white@0.3:draw=full:rate=60,format=rgba,colorkey=0x000000:0.1:0.1"
         );
     }
@@ -829,7 +878,7 @@ mod tests {
     fn test_get_filter_spectrum_circle() {
         let style = WaveformStyle::SpectrumCircle;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showspectrum=s=100x50:mode=combined:color=white:slide=scroll:overlap=0.9:rate=60,format=rgba, \
                      polar=r=min(w\\,h)/2,colorkey=0x000000:0.1:0.1"
         );
@@ -839,7 +888,7 @@ mod tests {
     fn test_get_filter_glow_wave_modern() {
         let style = WaveformStyle::GlowWaveModern;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=cline:rate=60:colors=#00e5ff,format=rgba[wave_raw]; \
                      [wave_raw]format=rgba,split[wave1][wave2]; \
                      [wave2]boxblur=5:2[halo]; \
@@ -851,13 +900,13 @@ mod tests {
     fn test_get_filter_equalizer_32_bands() {
         let style = WaveformStyle::Equalizer32Bands;
         let expected_filter = "asplit=32[AS01][AS02][AS03][AS04][AS05][AS06][AS07][AS08][AS09][AS10][AS11][AS12][AS13][AS14][AS15][AS16][AS17][AS18][AS19][AS20][AS21][AS22][AS23][AS24][AS25][AS26][AS27][AS28][AS29][AS30][AS31][AS32]; [AS01]bandpass=f=20:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v01]; [v01]split[bg01][fg01]; [bg01]boxblur=2:1[glow01]; [glow01][fg01]overlay=format=auto[EQ01]; [AS02]bandpass=f=25:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v02]; [v02]split[bg02][fg02]; [bg02]boxblur=2:1[glow02]; [glow02][fg02]overlay=format=auto[EQ02]; [AS03]bandpass=f=31:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v03]; [v03]split[bg03][fg03]; [bg03]boxblur=2:1[glow03]; [glow03][fg03]overlay=format=auto[EQ03]; [AS04]bandpass=f=40:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v04]; [v04]split[bg04][fg04]; [bg04]boxblur=2:1[glow04]; [glow04][fg04]overlay=format=auto[EQ04]; [AS05]bandpass=f=50:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v05]; [v05]split[bg05][fg05]; [bg05]boxblur=2:1[glow05]; [glow05][fg05]overlay=format=auto[EQ05]; [AS06]bandpass=f=63:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v06]; [v06]split[bg06][fg06]; [bg06]boxblur=2:1[glow06]; [glow06][fg06]overlay=format=auto[EQ06]; [AS07]bandpass=f=80:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v07]; [v07]split[bg07][fg07]; [bg07]boxblur=2:1[glow07]; [glow07][fg07]overlay=format=auto[EQ07]; [AS08]bandpass=f=100:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v08]; [v08]split[bg08][fg08]; [bg08]boxblur=2:1[glow08]; [glow08][fg08]overlay=format=auto[EQ08]; [AS09]bandpass=f=125:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v09]; [v09]split[bg09][fg09]; [bg09]boxblur=2:1[glow09]; [glow09][fg09]overlay=format=auto[EQ09]; [AS10]bandpass=f=160:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v10]; [v10]split[bg10][fg10]; [bg10]boxblur=2:1[glow10]; [glow10][fg10]overlay=format=auto[EQ10]; [AS11]bandpass=f=200:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v11]; [v11]split[bg11][fg11]; [bg11]boxblur=2:1[glow11]; [glow11][fg11]overlay=format=auto[EQ11]; [AS12]bandpass=f=250:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v12]; [v12]split[bg12][fg12]; [bg12]boxblur=2:1[glow12]; [glow12][fg12]overlay=format=auto[EQ12]; [AS13]bandpass=f=315:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v13]; [v13]split[bg13][fg13]; [bg13]boxblur=2:1[glow13]; [glow13][fg13]overlay=format=auto[EQ13]; [AS14]bandpass=f=400:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v14]; [v14]split[bg14][fg14]; [bg14]boxblur=2:1[glow14]; [glow14][fg14]overlay=format=auto[EQ14]; [AS15]bandpass=f=500:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v15]; [v15]split[bg15][fg15]; [bg15]boxblur=2:1[glow15]; [glow15][fg15]overlay=format=auto[EQ15]; [AS16]bandpass=f=630:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v16]; [v16]split[bg16][fg16]; [bg16]boxblur=2:1[glow16]; [glow16][fg16]overlay=format=auto[EQ16]; [AS17]bandpass=f=800:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v17]; [v17]split[bg17][fg17]; [bg17]boxblur=2:1[glow17]; [glow17][fg17]overlay=format=auto[EQ17]; [AS18]bandpass=f=1000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v18]; [v18]split[bg18][fg18]; [bg18]boxblur=2:1[glow18]; [glow18][fg18]overlay=format=auto[EQ18]; [AS19]bandpass=f=1250:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v19]; [v19]split[bg19][fg19]; [bg19]boxblur=2:1[glow19]; [glow19][fg19]overlay=format=auto[EQ19]; [AS20]bandpass=f=1500:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v20]; [v20]split[bg20][fg20]; [bg20]boxblur=2:1[glow20]; [glow20][fg20]overlay=format=auto[EQ20]; [AS21]bandpass=f=2000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v21]; [v21]split[bg21][fg21]; [bg21]boxblur=2:1[glow21]; [glow21][fg21]overlay=format=auto[EQ21]; [AS22]bandpass=f=2500:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v22]; [v22]split[bg22][fg22]; [bg22]boxblur=2:1[glow22]; [glow22][fg22]overlay=format=auto[EQ22]; [AS23]bandpass=f=3150:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v23]; [v23]split[bg23][fg23]; [bg23]boxblur=2:1[glow23]; [glow23][fg23]overlay=format=auto[EQ23]; [AS24]bandpass=f=4000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v24]; [v24]split[bg24][fg24]; [bg24]boxblur=2:1[glow24]; [glow24][fg24]overlay=format=auto[EQ24]; [AS25]bandpass=f=5000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v25]; [v25]split[bg25][fg25]; [bg25]boxblur=2:1[glow25]; [glow25][fg25]overlay=format=auto[EQ25]; [AS26]bandpass=f=6300:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v26]; [v26]split[bg26][fg26]; [bg26]boxblur=2:1[glow26]; [glow26][fg26]overlay=format=auto[EQ26]; [AS27]bandpass=f=8000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v27]; [v27]split[bg27][fg27]; [bg27]boxblur=2:1[glow27]; [glow27][fg27]overlay=format=auto[EQ27]; [AS28]bandpass=f=12000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v28]; [v28]split[bg28][fg28]; [bg28]boxblur=2:1[glow28]; [glow28][fg28]overlay=format=auto[EQ28]; [AS29]bandpass=f=16000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v29]; [v29]split[bg29][fg29]; [bg29]boxblur=2:1[glow29]; [glow29][fg29]overlay=format=auto[EQ29]; [AS30]bandpass=f=20000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v30]; [v30]split[bg30][fg30]; [bg30]boxblur=2:1[glow30]; [glow30][fg30]overlay=format=auto[EQ30]; [AS31]bandpass=f=22000:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v31]; [v31]split[bg31][fg31]; [bg31]boxblur=2:1[glow31]; [glow31][fg31]overlay=format=auto[EQ31]; [AS32]bandpass=f=22050:w=4,showwaves=s=19x720:mode=cline:r=60:colors=#ffffff[v32]; [v32]split[bg32][fg32]; [bg32]boxblur=2:1[glow32]; [glow32][fg32]overlay=format=auto[EQ32]; color=s=9x720:c=black:r=60,split=33[G01][G02][G03][G04][G05][G06][G07][G08][G09][G10][G11][G12][G13][G14][G15][G16][G17][G18][G19][G20][G21][G22][G23][G24][G25][G26][G27][G28][G29][G30][G31][G32][G33]; [G01][EQ01][G02][EQ02][G03][EQ03][G04][EQ04][G05][EQ05][G06][EQ06][G07][EQ07][G08][EQ08][G09][EQ09][G10][EQ10][G11][EQ11][G12][EQ12][G13][EQ13][G14][EQ14][G15][EQ15][G16][EQ16][G17][EQ17][G18][EQ18][G19][EQ19][G20][EQ20][G21][EQ21][G22][EQ22][G23][EQ23][G24][EQ24][G25][EQ25][G26][EQ26][G27][EQ27][G28][EQ28][G29][EQ29][G30][EQ30][G31][EQ31][G32][EQ32][G33]hstack=inputs=65[BARS]; [BARS]format=rgba,colorkey=0x000000:0.1:0.1";
-        assert_eq!(style.get_filter(1280, 720, None, None), expected_filter);
+        assert_eq!(style.get_filter(1280, 720, None, 60), expected_filter);
     }
 
     #[test]
     fn test_get_filter_equalizer_10_bands() {
         let style = WaveformStyle::Equalizer10Bands;
-        let filter = style.get_filter(1260, 720, None, None);
+        let filter = style.get_filter(1260, 720, None, 60);
         // 1260 / 21 = 60px por unidad. gap_w será 30px.
         assert!(filter.contains("asplit=10"));
         assert!(filter.contains("hstack=inputs=21"));
@@ -868,7 +917,7 @@ mod tests {
     fn test_get_filter_circular_wave() {
         let style = WaveformStyle::CircularWave;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=cline:colors=white:draw=full:rate=60,format=rgba,split[fill][border]; [fill]colorchannelmixer=aa=0.3[fill_t]; [fill_t][border]overlay=format=auto, geq='p(mod(W/PI*(PI+atan2(H/2-Y,X-W/2)),W), H-2*hypot(H/2-Y,X-W/2))':a='if(eq(alpha(mod(W/PI*(PI+atan2(H/2-Y,X-W/2)),W), H-2*hypot(H/2-Y,X-W/2)),0),0, if(p(mod(W/PI*(PI+atan2(H/2-Y,X-W/2)),W), H-2*hypot(H/2-Y,X-W/2)),255,0))', colorkey=0x000000:0.1:0.1"
         );
     }
@@ -877,8 +926,19 @@ mod tests {
     fn test_get_filter_smooth_line() {
         let style = WaveformStyle::SmoothLine;
         assert_eq!(
-            style.get_filter(100, 50, None, None),
+            style.get_filter(100, 50, None, 60),
             "showwaves=s=100x50:mode=line:colors=white:rate=60,format=rgba,colorkey=0x000000:0.1:0.1,boxblur=1"
         );
     }
+
+    #[test]
+    fn test_catalog_names_match_yaml() {
+        assert_eq!(CATALOG.len(), 37);
+        for info in CATALOG {
+            let parsed: WaveformStyle = serde_yaml::from_str(info.name)
+                .unwrap_or_else(|e| panic!("'{}' no se deserializa: {e}", info.name));
+            assert_eq!(parsed, info.style, "'{}'", info.name);
+            assert_eq!(info.style.name(), info.name);
+        }
+    }
 }
@@ -1,8 +1,14 @@
 use serde::Deserialize;
 use super::video::VideoSettings;
+use super::encoding::{DEFAULT_FPS, EncodingSettings};
 use super::background::BackgroundSettings;
+use super::grading::Grading;
 use super::waveform::WaveformSettings;
-use super::text::TextSettings;
+use super::text::{BoxSettings, TextAlign, TextSettings};
+use super::show::ShowSettings;
+use crate::captions::{self, CaptionSettings, Cue};
+use std::path::Path;
+use crate::placeholder::{self, Fields};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Template {
@@ -11,30 +17,214 @@ pub struct Template {
     pub waveform: WaveformSettings,
     pub title: Option<TextSettings>,
     pub subtitle: Option<TextSettings>,
+    /// Capas de texto adicionales, dibujadas en orden sobre el título y el subtítulo.
+    #[serde(default)]
+    pub texts: Vec<TextSettings>,
+    #[serde(default)]
+    pub encoding: EncodingSettings,
+    /// Sobreescribe los valores de `show` de la configuración para esta plantilla.
+    #[serde(default)]
+    pub show: ShowSettings,
+    /// Estilo y posición de los subtítulos de `--captions`.
+    #[serde(default)]
+    pub captions: Option<CaptionSettings>,
+    /// Retoque final de todo el video (onda, textos y subtítulos incluidos).
+    #[serde(default)]
+    pub post: Option<Grading>,
 }
 
 impl Template {
-    pub fn build_filter_complex(&self, title_text: &str, subtitle_text: &str) -> String {
-        let bg_scale = self.background.to_filter(self.video.width, self.video.height);
-        let wave_pipe = self.waveform.to_filter_chain();
-        let draw_title = self.title.as_ref().map(|title| title.to_drawtext(title_text));
-        let draw_subtitle = self.subtitle.as_ref().map(|subtitle| subtitle.to_drawtext(subtitle_text));
-        let title_and_subtitle = match (draw_title, draw_subtitle) {
-            (Some(t), Some(s)) => format!("[v1];[v1]{t},{s}[outv]"),
-            (Some(t), None) => format!("[v1];[v1]{t}[outv]"),
-            (None, Some(s)) => format!("[v1];[v1]{s}[outv]"),
-            (None, None) => "[outv]".to_string(),
+    /// Frame rate de salida: el de `encoding`, el de `video` o el valor por defecto.
+    pub fn fps(&self) -> u32 {
+        self.encoding.fps.or(self.video.fps).unwrap_or(DEFAULT_FPS)
+    }
+
+    /// Revisa la plantilla y devuelve la lista de problemas encontrados (vacía si es válida).
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.video.width == 0 || self.video.height == 0 {
+            problems.push("video: el ancho y el alto deben ser mayores que 0".to_string());
+        } else if !self.video.width.is_multiple_of(2) || !self.video.height.is_multiple_of(2) {
+            problems.push(format!(
+                "video: {}x{} no es compatible con yuv420p (ancho y alto deben ser pares)",
+                self.video.width, self.video.height
+            ));
+        }
+
+        problems.extend(self.background.validate(self.video.height));
+        if let Some(post) = &self.post {
+            problems.extend(post.validate("post"));
+        }
+
+        if self.video.fps == Some(0) {
+            problems.push("video: fps debe ser mayor que 0".to_string());
+        }
+        problems.extend(self.encoding.validate());
+
+        if self.waveform.width == 0 || self.waveform.height == 0 {
+            problems.push("waveform: el ancho y el alto deben ser mayores que 0".to_string());
+        }
+
+        for (name, layer, _) in self.text_layers() {
+            problems.extend(layer.validate(&name));
+        }
+        if let Some(captions) = &self.captions {
+            problems.extend(captions.validate());
+        }
+        for (i, layer) in self.texts.iter().enumerate() {
+            if layer.text.as_deref().is_none_or(str::is_empty) {
+                problems.push(format!("texts[{i}]: falta el campo text"));
+            }
+        }
+
+        problems
+    }
+
+    /// Capas de texto en orden de dibujo, con su nombre y su texto por defecto.
+    pub fn text_layers(&self) -> impl Iterator<Item = (String, &TextSettings, &'static str)> {
+        let fixed = [
+            ("title", self.title.as_ref(), "{title}"),
+            ("subtitle", self.subtitle.as_ref(), "{subtitle}"),
+        ];
+        fixed
+            .into_iter()
+            .filter_map(|(name, layer, default)| layer.map(|l| (name.to_string(), l, default)))
+            .chain(
+                self.texts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, layer)| (format!("texts[{i}]"), layer, "")),
+            )
+    }
+
+    /// Cambia las familias de las capas por los archivos que resuelve fontconfig.
+    pub fn resolve_fonts(&mut self) -> Result<(), String> {
+        let layers = self
+            .title
+            .iter_mut()
+            .map(|l| ("title".to_string(), l))
+            .chain(self.subtitle.iter_mut().map(|l| ("subtitle".to_string(), l)))
+            .chain(self.texts.iter_mut().enumerate().map(|(i, l)| (format!("texts[{i}]"), l)))
+            .chain(self.captions.iter_mut().map(|c| ("captions".to_string(), &mut c.style)));
+        for (name, layer) in layers {
+            layer.resolve_font(&name)?;
+        }
+        Ok(())
+    }
+
+    /// Estilo de los subtítulos: el de `captions` o uno por defecto (texto blanco
+    /// sobre una caja oscura, centrado abajo y en dos líneas como máximo).
+    pub fn caption_settings(&self) -> CaptionSettings {
+        let mut settings = self.captions.clone().unwrap_or_else(|| CaptionSettings {
+            style: TextSettings {
+                font: "sans-serif".to_string(),
+                size: (self.video.height / 20).max(12),
+                color: "white".to_string(),
+                x: "(w-text_w)/2".to_string(),
+                y: format!("h-text_h-{}", self.video.height / 12),
+                align: TextAlign::Center,
+                text_box: Some(BoxSettings { color: "black@0.6".to_string(), padding: 12 }),
+                ..Default::default()
+            },
+            highlight: None,
+        });
+        settings.style.max_width.get_or_insert(self.video.width * 8 / 10);
+        settings.style.max_lines.get_or_insert(2);
+        settings
+    }
+
+    /// Guion ASS de los subtítulos (ver [`captions::to_ass`]), con los tiempos
+    /// desplazados para que el cero sea el punto de búsqueda `seek`.
+    pub fn captions_script(&self, cues: &[Cue], seek: f64) -> String {
+        let cues = captions::shift(cues, seek);
+        captions::to_ass(&cues, &self.caption_settings(), self.video.width, self.video.height)
+    }
+
+    /// Grafo de filtros completo. Los textos se expanden con `fields` y el guion de
+    /// subtítulos `captions` (ver [`Template::captions_script`]) se quema encima de
+    /// todas las capas.
+    pub fn build_filter_complex(&self, fields: &Fields, captions: Option<&Path>, seek: f64) -> String {
+        let bg_scale = self.background.to_filter(self.video.width, self.video.height, true);
+        self.with_post(self.compose(&bg_scale, "auto", fields, self.subtitles(captions, false), seek))
+    }
+
+    /// Grafo para un fondo de video: se pasa a los `fps` del resultado para que la
+    /// onda no herede la cadencia del video y después se escala como una imagen.
+    pub fn build_video_filter_complex(&self, fields: &Fields, captions: Option<&Path>, seek: f64, fps: u32) -> String {
+        let bg_scale = self.background.to_filter(self.video.width, self.video.height, false);
+        let subtitles = self.subtitles(captions, false);
+        self.with_post(self.compose(&format!("fps={fps},{bg_scale}"), "auto", fields, subtitles, seek))
+    }
+
+    /// Grafo para un fondo generado (`background.generate`): la entrada 0 ya tiene el
+    /// tamaño del video, así que no se escala. Solo se retoca con los valores
+    /// explícitos de `grading`: el oscurecido por defecto es para fotos.
+    pub fn build_generated_filter_complex(&self, fields: &Fields, captions: Option<&Path>, seek: f64) -> String {
+        let grading = match self.background.grading.to_filter(0.0, 1.0) {
+            grading if grading.is_empty() => "null".to_string(),
+            grading => grading,
+        };
+        self.with_post(self.compose(&grading, "auto", fields, self.subtitles(captions, false), seek))
+    }
+
+    /// Añade el retoque de `post` al final del grafo, que sigue terminando en `[outv]`.
+    /// No se usa en el modo transparente: los filtros de color no conservan el alfa.
+    fn with_post(&self, filter: String) -> String {
+        let post = self.post.as_ref().map(|p| p.to_filter(0.0, 1.0)).unwrap_or_default();
+        match filter.strip_suffix("[outv]") {
+            Some(graph) if !post.is_empty() => format!("{graph}[post];[post]{post}[outv]"),
+            _ => filter,
+        }
+    }
+
+    /// Grafo sin fondo: la entrada 0 es un lienzo transparente del tamaño del video
+    /// y la composición se hace en RGBA para conservar el canal alfa.
+    pub fn build_transparent_filter_complex(&self, fields: &Fields, captions: Option<&Path>, seek: f64) -> String {
+        self.compose("format=rgba", "rgb", fields, self.subtitles(captions, true), seek)
+    }
+
+    /// Filtro `subtitles` para el guion `captions`, si lo hay.
+    fn subtitles(&self, captions: Option<&Path>, alpha: bool) -> Option<String> {
+        captions.map(|path| captions::subtitles_filter(path, &self.caption_settings(), alpha))
+    }
+
+    /// Lienzo transparente para `-f lavfi` que sustituye a la imagen de fondo.
+    pub fn transparent_canvas(&self, fps: u32) -> String {
+        format!(
+            "color=c=black@0.0:s={}x{}:r={fps},format=rgba",
+            self.video.width, self.video.height
+        )
+    }
+
+    fn compose(&self, bg_scale: &str, overlay_format: &str, fields: &Fields, subtitles: Option<String>, seek: f64) -> String {
+        let wave_pipe = self.waveform.to_filter_chain(self.fps());
+        // El audio empieza en `seek`: las ventanas de las capas se desplazan para que
+        // coincidan con lo que se oye
+        let mut draws: Vec<String> = self
+            .text_layers()
+            .filter_map(|(_, layer, default)| Some((layer.shifted(seek)?, default)))
+            .flat_map(|(layer, default)| {
+                let text = placeholder::expand_drawtext(layer.text.as_deref().unwrap_or(default), fields);
+                layer.to_drawtexts(&text)
+            })
+            .collect();
+        draws.extend(subtitles);
+        let texts = if draws.is_empty() {
+            "[outv]".to_string()
+        } else {
+            format!("[v1];[v1]{}[outv]", draws.join(","))
         };
 
         format!(
             "[0:v]{bg_scale}[bg]; \
              [1:a]{wave_pipe}[wave]; \
-             [bg][wave]overlay={wx}:{wy}:format=auto{title_and_subtitle}",
+             [bg][wave]overlay={wx}:{wy}:format={overlay_format}{texts}",
             bg_scale = bg_scale,
             wave_pipe = wave_pipe,
             wx = self.waveform.x,
             wy = self.waveform.y,
-            title_and_subtitle = title_and_subtitle,
+            texts = texts,
         )
     }
 }
@@ -42,18 +232,19 @@ impl Template {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::style::WaveformStyle;
+    use crate::models::style::{CATALOG, WaveformStyle};
 
-    #[test]
-    fn test_build_filter_complex() {
-        let template = Template {
+    fn sample_template() -> Template {
+        Template {
             video: VideoSettings {
                 width: 1920,
                 height: 1080,
+                fps: None,
             },
             background: BackgroundSettings {
                 path: "background.png".to_string(),
                 mode: "stretch".to_string(),
+                ..Default::default()
             },
             waveform: WaveformSettings {
                 style: Some(WaveformStyle::ClassicLine),
@@ -71,6 +262,7 @@ mod tests {
                 color: "white".to_string(),
                 x: "(w-text_w)/2".to_string(),
                 y: "540".to_string(),
+                ..Default::default()
             }),
             subtitle: Some(TextSettings {
                 font: "Arial".to_string(),
@@ -78,11 +270,178 @@ mod tests {
                 color: "white".to_string(),
                 x: "(w-text_w)/2".to_string(),
                 y: "600".to_string(),
+                ..Default::default()
             }),
-        };
+            texts: Vec::new(),
+            encoding: EncodingSettings::default(),
+            show: ShowSettings::default(),
+            captions: None,
+            post: None,
+        }
+    }
+
+    fn sample_fields() -> Fields {
+        Fields::from([
+            ("title".to_string(), "My Title".to_string()),
+            ("subtitle".to_string(), "My Subtitle".to_string()),
+            ("album".to_string(), "Rust".to_string()),
+            ("episode".to_string(), "7".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_text_layer_format_string() {
+        let mut template = sample_template();
+        template.subtitle.as_mut().unwrap().text = Some("{album} · Ep. {episode:03}".to_string());
+        let filter = template.build_filter_complex(&sample_fields(), None, 0.0);
+        assert!(filter.contains("drawtext=text='My Title'"));
+        assert!(filter.contains("drawtext=text='Rust · Ep. 007'"));
+    }
+
+    #[test]
+    fn test_extra_text_layers_are_chained() {
+        let mut template = sample_template();
+        template.subtitle = None;
+        template.texts = vec![
+            TextSettings {
+                text: Some("{album}".to_string()),
+                font: "Arial".to_string(),
+                size: 20,
+                color: "yellow".to_string(),
+                x: "10".to_string(),
+                y: "10".to_string(),
+                ..Default::default()
+            },
+            TextSettings {
+                text: Some("Suscríbete".to_string()),
+                font: "Arial".to_string(),
+                size: 20,
+                color: "white".to_string(),
+                x: "10".to_string(),
+                y: "h-40".to_string(),
+                start: Some(60.0),
+                ..Default::default()
+            },
+        ];
+        let filter = template.build_filter_complex(&sample_fields(), None, 0.0);
+        assert!(filter.ends_with(
+            "[v1];[v1]drawtext=text='My Title':font='Arial':fontsize=64:fontcolor=white:x=(w-text_w)/2:y=540,\
+             drawtext=text='Rust':font='Arial':fontsize=20:fontcolor=yellow:x=10:y=10,\
+             drawtext=text='Suscríbete':font='Arial':fontsize=20:fontcolor=white:x=10:y=h-40:enable='gte(t,60)'[outv]"
+        ));
+
+        template.title = None;
+        template.texts.clear();
+        assert!(template.build_filter_complex(&sample_fields(), None, 0.0).ends_with("format=auto[outv]"));
+    }
+
+    #[test]
+    fn test_captions_are_drawn_last() {
+        let template = sample_template();
+        let filter = template.build_filter_complex(&sample_fields(), Some(Path::new("/tmp/subs.ass")), 0.0);
+        assert!(filter.ends_with("y=600,subtitles=filename=/tmp/subs.ass[outv]"));
+        let transparent = template.build_transparent_filter_complex(&sample_fields(), Some(Path::new("/tmp/subs.ass")), 0.0);
+        assert!(transparent.ends_with(",subtitles=filename=/tmp/subs.ass:alpha=1[outv]"));
+
+        let cues = [Cue {
+            start: 1.0,
+            end: 2.5,
+            text: "Hola: ¿qué tal?".to_string(),
+            words: Vec::new(),
+        }];
+        let script = template.captions_script(&cues, 0.0);
+        assert!(script.contains(
+            "Style: Captions,sans-serif,64.80,&H00FFFFFF,&H00FFFFFF,&H66000000,&H00000000,0,0,0,0,100,100,0,0,3,12,0,7,0,0,0,1\n"
+        ));
+        assert!(script.ends_with(
+            "Dialogue: 0,0:00:01.00,0:00:02.50,Captions,,0,0,0,,{\\an8\\pos(960,925)}Hola: ¿qué tal?\n"
+        ));
+        assert_eq!(template.caption_settings().style.max_width, Some(1536));
+    }
+
+    #[test]
+    fn test_karaoke_captions_with_every_style() {
+        let mut template = sample_template();
+        let cues = captions::parse_json(
+            r#"{"segments": [{"start": 0, "end": 2, "words": [
+                {"word": "Hola", "start": 0, "end": 1}, {"word": "mundo", "start": 1, "end": 2}
+            ]}]}"#,
+        )
+        .unwrap();
+        let script = template.captions_script(&cues, 0.0);
+        assert!(script.ends_with(concat!(
+            r"Dialogue: 0,0:00:00.00,0:00:02.00,Captions,,0,0,0,,{\an8\pos(960,925)}",
+            r"{\1c&H00FFFF&\t(1000,1000,\1c&HFFFFFF&)}Hola ",
+            r"{\1c&HFFFFFF&\t(1000,1000,\1c&H00FFFF&)\t(2000,2000,\1c&HFFFFFF&)}mundo",
+            "\n"
+        )));
+        for info in CATALOG {
+            template.waveform.style = Some(info.style);
+            let filter = template.build_filter_complex(&sample_fields(), Some(Path::new("subs.ass")), 0.0);
+            assert!(filter.contains("[wave]; [bg][wave]overlay="), "{}", info.name);
+            assert!(filter.ends_with(",subtitles=filename=subs.ass[outv]"), "{}", info.name);
+        }
+    }
 
-        let filter = template.build_filter_complex("My Title", "My Subtitle");
-        let expected = "[0:v]scale=1920:1080,eq=brightness=-0.1:saturation=0.95[bg]; [1:a]showwaves=s=800x300:mode=line:colors=cyan:rate=60,format=rgba,colorkey=0x000000:0.1:0.1[wave]; [bg][wave]overlay=100:200:format=auto[v1];[v1]drawtext=text='My Title':fontfile='Arial':fontsize=64:fontcolor=white:x=(w-text_w)/2:y=540,drawtext=text='My Subtitle':fontfile='Arial':fontsize=32:fontcolor=white:x=(w-text_w)/2:y=600[outv]";
+    #[test]
+    fn test_build_filter_complex() {
+        let template = sample_template();
+        let filter = template.build_filter_complex(&sample_fields(), None, 0.0);
+        let expected = "[0:v]scale=1920:1080,eq=brightness=-0.1:saturation=0.95[bg]; [1:a]showwaves=s=800x300:mode=line:colors=cyan:rate=30,format=rgba,colorkey=0x000000:0.1:0.1[wave]; [bg][wave]overlay=100:200:format=auto[v1];[v1]drawtext=text='My Title':font='Arial':fontsize=64:fontcolor=white:x=(w-text_w)/2:y=540,drawtext=text='My Subtitle':font='Arial':fontsize=32:fontcolor=white:x=(w-text_w)/2:y=600[outv]";
         assert_eq!(filter, expected);
     }
+
+    #[test]
+    fn test_post_grades_the_final_frame() {
+        let mut template = sample_template();
+        template.post = Some(Grading { vignette: Some(0.2), grain: Some(8), ..Default::default() });
+        let filter = template.build_filter_complex(&sample_fields(), None, 0.0);
+        assert!(filter.contains("y=600[post];[post]vignette=angle=0.3142,noise=alls=8:allf=t+u[outv]"));
+        assert!(filter.ends_with("[outv]"));
+
+        let transparent = template.build_transparent_filter_complex(&sample_fields(), None, 0.0);
+        assert!(!transparent.contains("[post]"));
+
+        template.post = Some(Grading::default());
+        assert!(!template.build_filter_complex(&sample_fields(), None, 0.0).contains("[post]"));
+    }
+
+    #[test]
+    fn test_build_transparent_filter_complex() {
+        let template = sample_template();
+        let filter = template.build_transparent_filter_complex(&sample_fields(), None, 0.0);
+        assert!(filter.starts_with("[0:v]format=rgba[bg]; [1:a]showwaves"));
+        assert!(filter.contains("[bg][wave]overlay=100:200:format=rgb[v1]"));
+        assert!(!filter.contains("eq=brightness"));
+        assert_eq!(
+            template.transparent_canvas(30),
+            "color=c=black@0.0:s=1920x1080:r=30,format=rgba"
+        );
+    }
+
+    #[test]
+    fn test_fps_precedence() {
+        let mut template = sample_template();
+        assert_eq!(template.fps(), 30);
+        template.video.fps = Some(25);
+        assert_eq!(template.fps(), 25);
+        template.encoding.fps = Some(60);
+        assert_eq!(template.fps(), 60);
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        let mut template = sample_template();
+        template.video.width = 1921;
+        template.background.mode = "zoom".to_string();
+        template.title.as_mut().unwrap().font = "/no/existe/Ubuntu-B.ttf".to_string();
+        template.subtitle.as_mut().unwrap().font = "sans-serif".to_string();
+
+        let problems = template.validate();
+        assert_eq!(problems.len(), 4);
+        assert!(problems[0].starts_with("video:"));
+        assert!(problems.iter().any(|p| p.contains("'zoom'")));
+        assert!(problems.iter().any(|p| p.contains("background.png")));
+        assert!(problems.iter().any(|p| p.starts_with("title:")));
+    }
 }
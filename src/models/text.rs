@@ -1,31 +1,421 @@
+use super::animation::{self, AnimationSettings};
+use crate::escape;
+use crate::font::{self, FontMatch, FontSpec};
+use crate::layout::{self, Metrics};
+use log::warn;
+use regex::Regex;
 use serde::Deserialize;
+use std::sync::LazyLock;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct TextSettings {
+    /// Texto con campos como `{album} · Ep. {episode:03}`. Sin él, el título usa
+    /// `{title}` y el subtítulo `{subtitle}`.
+    #[serde(default)]
+    pub text: Option<String>,
     pub font: String,
     pub size: u32,
     pub color: String,
     pub x: String,
     pub y: String,
+    /// Segundo del video en el que aparece la capa.
+    #[serde(default)]
+    pub start: Option<f64>,
+    /// Segundo del video en el que desaparece la capa.
+    #[serde(default)]
+    pub end: Option<f64>,
+    /// Ancho máximo en píxeles; si se indica, el texto se parte en líneas y se
+    /// reduce hasta que cabe.
+    #[serde(default)]
+    pub max_width: Option<u32>,
+    /// Máximo de líneas al partir el texto (sin límite por defecto).
+    #[serde(default)]
+    pub max_lines: Option<u32>,
+    /// Tamaño mínimo al reducir el texto (por defecto, la mitad de `size`).
+    #[serde(default)]
+    pub min_size: Option<u32>,
+    /// Alineación de las líneas dentro del bloque.
+    #[serde(default)]
+    pub align: TextAlign,
+    #[serde(default)]
+    pub shadow: Option<ShadowSettings>,
+    /// Contorno alrededor de las letras.
+    #[serde(default)]
+    pub border: Option<BorderSettings>,
+    /// Caja de color detrás del texto.
+    #[serde(default, rename = "box")]
+    pub text_box: Option<BoxSettings>,
+    /// Píxeles extra entre líneas.
+    #[serde(default)]
+    pub line_spacing: Option<i32>,
+    /// Opacidad de la capa, de 0 a 1.
+    #[serde(default)]
+    pub alpha: Option<f64>,
+    /// Fundidos, deslizamiento y máquina de escribir.
+    #[serde(default)]
+    pub animation: Option<AnimationSettings>,
 }
 
+/// Una línea ya colocada, con su ancho si se ha medido.
+struct Line {
+    text: String,
+    size: u32,
+    x: String,
+    y: String,
+    width: Option<f64>,
+}
+
+/// Sombra desplazada `x`, `y` píxeles.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ShadowSettings {
+    pub color: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self { color: "black@0.6".to_string(), x: 3, y: 3 }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BorderSettings {
+    pub width: u32,
+    pub color: String,
+}
+
+impl Default for BorderSettings {
+    fn default() -> Self {
+        Self { width: 2, color: "black".to_string() }
+    }
+}
+
+/// Caja detrás del texto con `padding` píxeles de margen.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct BoxSettings {
+    pub color: String,
+    pub padding: u32,
+}
+
+impl Default for BoxSettings {
+    fn default() -> Self {
+        Self { color: "black@0.5".to_string(), padding: 16 }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// `text_w`/`tw` y `text_h`/`th` en las expresiones de posición.
+/// Pasos máximos de `animation.typewriter`. Un texto más largo se revela en
+/// grupos de letras para no generar un drawtext por cada una.
+pub const TYPEWRITER_MAX_STEPS: usize = 64;
+
+static TEXT_W: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(text_w|tw)\b").expect("Regex válida"));
+static TEXT_H: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(text_h|th)\b").expect("Regex válida"));
+
 impl TextSettings {
+    /// Filtro drawtext para `text`, que ya debe venir escapado para drawtext
+    /// (ver [`placeholder::expand_drawtext`](crate::placeholder::expand_drawtext)).
+    /// El resto de valores se escapan aquí para la opción y el filtergraph.
     pub fn to_drawtext(&self, text: &str) -> String {
-        format!(
-            "drawtext=text='{text}':fontfile='{font}':fontsize={size}:fontcolor={color}:x={x}:y={y}",
-            text = text,
-            font = self.font,
-            size = self.size,
-            color = self.color,
-            x = self.x,
-            y = self.y
-        )
+        self.drawtext(text, self.size, &self.x, &self.y, self.enable_expr())
+    }
+
+    /// Filtros drawtext de la capa. Con `max_width` el texto se ajusta y se dibuja
+    /// con un drawtext por línea; con `animation.typewriter`, con uno por paso
+    /// (ver [`TYPEWRITER_MAX_STEPS`]).
+    ///
+    /// En `x` e `y`, `text_w` y `text_h` pasan a ser el ancho y el alto del bloque;
+    /// `align` coloca cada línea dentro de él.
+    pub fn to_drawtexts(&self, text: &str) -> Vec<String> {
+        let lines = self.lines(text);
+        match self.animation.as_ref().and_then(|a| a.typewriter) {
+            Some(duration) => self.typewriter(&lines, duration),
+            None => lines
+                .iter()
+                .map(|line| self.drawtext(&line.text, line.size, &line.x, &line.y, self.enable_expr()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> Metrics {
+        match font::chain(&self.font).into_iter().next() {
+            Some(FontSpec::Path(path)) => Metrics::load(&path),
+            _ => Metrics::Approx,
+        }
+    }
+
+    /// Líneas con su tamaño y posición; sin `max_width` hay una sola.
+    fn lines(&self, text: &str) -> Vec<Line> {
+        let Some(max_width) = self.max_width else {
+            return vec![Line {
+                text: text.to_string(),
+                size: self.size,
+                x: self.x.clone(),
+                y: self.y.clone(),
+                width: None,
+            }];
+        };
+        let min_size = self.min_size.unwrap_or(self.size / 2);
+        let mut block = layout::fit(text, &self.metrics(), self.size, min_size, max_width, self.max_lines);
+        block.line_height += self.line_spacing.unwrap_or(0) as f64;
+
+        let block_w = format!("{:.0}", block.width());
+        let block_h = format!("{:.0}", block.height());
+        let x = TEXT_W.replace_all(&self.x, block_w.as_str());
+        let y = TEXT_H.replace_all(&self.y, block_h.as_str());
+        let x = match self.align {
+            TextAlign::Left => x.into_owned(),
+            TextAlign::Center => format!("({x})+({block_w}-text_w)/2"),
+            TextAlign::Right => format!("({x})+{block_w}-text_w"),
+        };
+        block
+            .lines
+            .iter()
+            .zip(&block.widths)
+            .enumerate()
+            .map(|(i, (line, width))| Line {
+                text: line.clone(),
+                size: block.size,
+                x: x.clone(),
+                y: match i {
+                    0 => y.to_string(),
+                    i => format!("({y})+{:.0}", block.line_height * i as f64),
+                },
+                width: Some(*width),
+            })
+            .collect()
+    }
+
+    /// Un drawtext por cada prefijo del texto, visible hasta que aparece el
+    /// siguiente. Cada paso añade una letra o, en textos de más de
+    /// [`TYPEWRITER_MAX_STEPS`] letras, un grupo de ellas. `text_w` se fija al
+    /// ancho final para que el texto no se mueva.
+    fn typewriter(&self, lines: &[Line], duration: f64) -> Vec<String> {
+        let start = self.start.unwrap_or(0.0);
+        let units: Vec<Vec<&str>> = lines.iter().map(|l| animation::reveal_units(&l.text)).collect();
+        let letters = units.iter().map(Vec::len).sum::<usize>();
+        let per_step = letters.div_ceil(TYPEWRITER_MAX_STEPS).max(1);
+        let total = units.iter().map(|u| u.len().div_ceil(per_step)).sum::<usize>().max(1);
+        let step = duration / total as f64;
+        let metrics = self.metrics();
+
+        let mut draws = Vec::new();
+        let mut shown = 0;
+        for (line, units) in lines.iter().zip(&units) {
+            let width = line
+                .width
+                .unwrap_or_else(|| metrics.width(&layout::visible(&line.text), line.size));
+            let x = TEXT_W.replace_all(&line.x, format!("{width:.0}").as_str());
+            let steps = units.len().div_ceil(per_step);
+            let mut prefix = String::new();
+            for (i, group) in units.chunks(per_step).enumerate() {
+                prefix.push_str(&group.concat());
+                let from = start + step * (shown + i) as f64;
+                // Prefijos que ya se han sustituido antes del punto de búsqueda
+                if i + 1 < steps && from + step <= 0.0 {
+                    continue;
+                }
+                let enable = if i + 1 < steps {
+                    Some(format!("gte(t,{from})*lt(t,{})", from + step))
+                } else {
+                    window_expr(Some(from), self.end)
+                };
+                draws.push(self.drawtext(&prefix, line.size, &x, &line.y, enable));
+            }
+            shown += steps;
+        }
+        draws
+    }
+
+    fn drawtext(&self, text: &str, size: u32, x: &str, y: &str, enable: Option<String>) -> String {
+        let start = self.start.unwrap_or(0.0);
+        let (x, y) = match &self.animation {
+            Some(animation) => animation.slide_exprs(x, y, start),
+            None => (x.to_string(), y.to_string()),
+        };
+        let drawtext = format!(
+            "drawtext=text={text}:{font}:fontsize={size}:fontcolor={color}:x={x}:y={y}",
+            text = escape::quote(&escape::option_value(text)),
+            font = self.font_option(),
+            size = size,
+            color = escape::filter_arg(&self.color),
+            x = escape::filter_arg(&x),
+            y = escape::filter_arg(&y)
+        ) + &self.style_options();
+        match enable {
+            Some(expr) => format!("{drawtext}:enable={}", escape::quote(&escape::option_value(&expr))),
+            None => drawtext,
+        }
+    }
+
+    /// Opciones de sombra, contorno, caja, interlineado y opacidad.
+    fn style_options(&self) -> String {
+        let mut options = String::new();
+        if let Some(shadow) = &self.shadow {
+            options += &format!(
+                ":shadowcolor={}:shadowx={}:shadowy={}",
+                escape::filter_arg(&shadow.color),
+                shadow.x,
+                shadow.y
+            );
+        }
+        if let Some(border) = &self.border {
+            options += &format!(
+                ":borderw={}:bordercolor={}",
+                border.width,
+                escape::filter_arg(&border.color)
+            );
+        }
+        if let Some(text_box) = &self.text_box {
+            options += &format!(
+                ":box=1:boxcolor={}:boxborderw={}",
+                escape::filter_arg(&text_box.color),
+                text_box.padding
+            );
+        }
+        if let Some(spacing) = self.line_spacing {
+            options += &format!(":line_spacing={spacing}");
+        }
+        let alpha = match &self.animation {
+            Some(animation) => animation.alpha_expr(self.alpha, self.start.unwrap_or(0.0), self.end),
+            None => self.alpha.map(|alpha| alpha.to_string()),
+        };
+        if let Some(alpha) = alpha {
+            options += &format!(":alpha={}", escape::filter_arg(&alpha));
+        }
+        options
+    }
+
+    /// `fontfile` si la fuente es un archivo; si no, `font` con el patrón de
+    /// fontconfig de la primera opción para que lo resuelva FFmpeg.
+    fn font_option(&self) -> String {
+        match font::chain(&self.font).into_iter().next() {
+            Some(FontSpec::Path(path)) => format!(
+                "fontfile={}",
+                escape::quote(&escape::option_value(&path.to_string_lossy()))
+            ),
+            Some(spec) => format!("font={}", escape::quote(&escape::option_value(&spec.pattern()))),
+            None => format!("fontfile={}", escape::quote(&escape::option_value(&self.font))),
+        }
+    }
+
+    /// Cambia `font` por el archivo que resuelve fontconfig. Devuelve el mensaje de
+    /// error si ninguna opción existe.
+    pub fn resolve_font(&mut self, name: &str) -> Result<(), String> {
+        match font::resolve(&self.font) {
+            FontMatch::Found(path) => self.font = path.to_string_lossy().into_owned(),
+            FontMatch::Substitute(path) => {
+                warn!("{name}: no se encuentra la fuente '{}', se usa {}", self.font, path.display());
+                self.font = path.to_string_lossy().into_owned();
+            }
+            FontMatch::Unchecked => {}
+            FontMatch::Missing => return Err(format!("{name}: no existe la fuente '{}'", self.font)),
+        }
+        Ok(())
+    }
+
+    /// Copia de la capa con `start` y `end` adelantados `offset` segundos, para
+    /// cuando el audio empieza en un punto de búsqueda (`--start`, `--frame`).
+    /// `None` si la ventana ya ha terminado en ese punto.
+    ///
+    /// Con animación, `start` se conserva aunque quede en negativo: es el origen
+    /// de los fundidos, el deslizamiento y la máquina de escribir.
+    pub fn shifted(&self, offset: f64) -> Option<TextSettings> {
+        if offset <= 0.0 {
+            return Some(self.clone());
+        }
+        if self.end.is_some_and(|end| end <= offset) {
+            return None;
+        }
+        Some(TextSettings {
+            start: match &self.animation {
+                Some(_) => Some(self.start.unwrap_or(0.0) - offset),
+                None => self.start.map(|start| start - offset).filter(|start| *start > 0.0),
+            },
+            end: self.end.map(|end| end - offset),
+            ..self.clone()
+        })
+    }
+
+    /// Expresión `enable` de FFmpeg para la ventana de visibilidad.
+    fn enable_expr(&self) -> Option<String> {
+        window_expr(self.start, self.end)
+    }
+
+    /// Problemas de la capa; `name` identifica la capa en los mensajes.
+    pub fn validate(&self, name: &str) -> Vec<String> {
+        let mut problems = Vec::new();
+        match font::resolve(&self.font) {
+            FontMatch::Missing => problems.push(format!("{name}: no existe la fuente '{}'", self.font)),
+            FontMatch::Substitute(path) => problems.push(format!(
+                "{name}: no se encuentra la fuente '{}' (fontconfig usaría {})",
+                self.font,
+                path.display()
+            )),
+            FontMatch::Found(_) | FontMatch::Unchecked => {}
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end)
+            && start >= end
+        {
+            problems.push(format!("{name}: start ({start}) debe ser menor que end ({end})"));
+        }
+        if self.start.is_some_and(|s| s < 0.0) || self.end.is_some_and(|e| e < 0.0) {
+            problems.push(format!("{name}: start y end no pueden ser negativos"));
+        }
+        if let Some(animation) = &self.animation {
+            let window = self.end.map(|end| end - self.start.unwrap_or(0.0));
+            problems.extend(animation.validate(name, self.end, window));
+        }
+        if self.alpha.is_some_and(|a| !(0.0..=1.0).contains(&a)) {
+            problems.push(format!("{name}: alpha debe estar entre 0 y 1"));
+        }
+        if self.max_width == Some(0) || self.max_lines == Some(0) {
+            problems.push(format!("{name}: max_width y max_lines deben ser mayores que 0"));
+        }
+        if let Some(min_size) = self.min_size
+            && (min_size == 0 || min_size > self.size)
+        {
+            problems.push(format!(
+                "{name}: min_size ({min_size}) debe estar entre 1 y size ({})",
+                self.size
+            ));
+        }
+        problems
+    }
+}
+
+/// Expresión `enable` para la ventana entre `start` y `end`. Un `start` negativo
+/// (capa desplazada por una búsqueda) ya ha pasado y no limita la ventana.
+fn window_expr(start: Option<f64>, end: Option<f64>) -> Option<String> {
+    match (start.filter(|start| *start >= 0.0), end) {
+        (Some(start), Some(end)) => Some(format!("between(t,{start},{end})")),
+        (Some(start), None) => Some(format!("gte(t,{start})")),
+        (None, Some(end)) => Some(format!("lte(t,{end})")),
+        (None, None) => None,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::animation::{Edge, SlideSettings};
+    use crate::escape::tests::{av_get_token, drawtext_expand};
+    use crate::placeholder::{self, Fields};
+    use proptest::prelude::*;
 
     #[test]
     fn test_to_drawtext() {
@@ -35,9 +425,228 @@ mod tests {
             color: "white".to_string(),
             x: "(w-text_w)/2".to_string(),
             y: "100".to_string(),
+            ..Default::default()
         };
         let text = "Hello World";
-        let expected = "drawtext=text='Hello World':fontfile='Arial':fontsize=48:fontcolor=white:x=(w-text_w)/2:y=100";
+        let expected = "drawtext=text='Hello World':font='Arial':fontsize=48:fontcolor=white:x=(w-text_w)/2:y=100";
         assert_eq!(settings.to_drawtext(text), expected);
     }
+
+    #[test]
+    fn test_font_family_uses_fontconfig_pattern() {
+        let mut settings = TextSettings {
+            font: "Ubuntu Bold, sans-serif".to_string(),
+            size: 48,
+            color: "white".to_string(),
+            x: "0".to_string(),
+            y: "0".to_string(),
+            ..Default::default()
+        };
+        assert!(settings.to_drawtext("A").contains(":font='Ubuntu\\:style=Bold':"));
+        settings.font = "/fonts/Ubuntu-B.ttf".to_string();
+        assert!(settings.to_drawtext("A").contains(":fontfile='/fonts/Ubuntu-B.ttf':"));
+        assert!(settings.resolve_font("title").is_err());
+    }
+
+    #[test]
+    fn test_long_text_is_wrapped_per_line() {
+        let settings = TextSettings {
+            font: "sans-serif".to_string(),
+            size: 50,
+            color: "white".to_string(),
+            x: "(w-text_w)/2".to_string(),
+            y: "h-text_h-20".to_string(),
+            max_width: Some(300),
+            max_lines: Some(3),
+            align: TextAlign::Center,
+            ..Default::default()
+        };
+        // Sin archivo de fuente se mide con la aproximación: 30 píxeles por carácter
+        let draws = settings.to_drawtexts("uno dos tres cuatro");
+        assert_eq!(draws.len(), 3);
+        assert!(draws[0].starts_with("drawtext=text='uno dos':"));
+        assert!(draws[0].contains(":fontsize=50:"));
+        assert!(draws[0].contains(":x=((w-210)/2)+(210-text_w)/2:y=h-180-20"));
+        assert!(draws[2].contains(":y=(h-180-20)+120"));
+
+        let single = TextSettings { max_width: None, ..settings };
+        assert_eq!(single.to_drawtexts("uno dos tres cuatro").len(), 1);
+    }
+
+    #[test]
+    fn test_style_options() {
+        let mut settings = TextSettings {
+            font: "/fonts/Ubuntu-B.ttf".to_string(),
+            size: 64,
+            color: "white".to_string(),
+            x: "10".to_string(),
+            y: "20".to_string(),
+            shadow: Some(ShadowSettings::default()),
+            border: Some(BorderSettings { width: 4, ..Default::default() }),
+            text_box: Some(BoxSettings::default()),
+            line_spacing: Some(12),
+            alpha: Some(0.8),
+            start: Some(2.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.to_drawtext("Hola"),
+            "drawtext=text='Hola':fontfile='/fonts/Ubuntu-B.ttf':fontsize=64:fontcolor=white:x=10:y=20\
+             :shadowcolor=black@0.6:shadowx=3:shadowy=3:borderw=4:bordercolor=black\
+             :box=1:boxcolor=black@0.5:boxborderw=16:line_spacing=12:alpha=0.8:enable='gte(t,2)'"
+        );
+        assert!(settings.validate("title").iter().all(|p| !p.contains("alpha")));
+
+        settings.alpha = Some(1.5);
+        assert!(settings.validate("title").iter().any(|p| p.contains("alpha")));
+    }
+
+    #[test]
+    fn test_style_from_yaml() {
+        let settings: TextSettings = serde_yaml::from_str(
+            "font: Ubuntu\nsize: 40\ncolor: white\nx: '0'\ny: '0'\n\
+             shadow: {x: 5}\nbox: {color: 'red@0.3'}\n",
+        )
+        .unwrap();
+        assert_eq!(settings.shadow, Some(ShadowSettings { x: 5, ..Default::default() }));
+        assert_eq!(settings.text_box.unwrap().color, "red@0.3");
+        assert_eq!(settings.border, None);
+    }
+
+    #[test]
+    fn test_title_card_with_fades_and_slide() {
+        let settings = TextSettings {
+            font: "sans-serif".to_string(),
+            size: 64,
+            color: "white".to_string(),
+            x: "(w-text_w)/2".to_string(),
+            y: "100".to_string(),
+            end: Some(10.0),
+            animation: Some(AnimationSettings {
+                fade_in: Some(0.5),
+                fade_out: Some(1.0),
+                slide: Some(SlideSettings { from: Edge::Top, duration: 0.5 }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let draw = settings.to_drawtext("Episodio 7");
+        assert!(draw.contains(":y='(100)-((100)+text_h)*pow(1-clip((t-0)/0.5,0,1),3)':"));
+        assert!(draw.ends_with(
+            ":alpha='clip(min((t-0)/0.5,(10-t)/1),0,1)':enable='lte(t,10)'"
+        ));
+        assert!(settings.validate("title").iter().all(|p| !p.contains("animation")));
+    }
+
+    #[test]
+    fn test_typewriter_reveals_prefixes() {
+        let settings = TextSettings {
+            font: "sans-serif".to_string(),
+            size: 50,
+            color: "white".to_string(),
+            x: "(w-text_w)/2".to_string(),
+            y: "100".to_string(),
+            start: Some(1.0),
+            animation: Some(AnimationSettings { typewriter: Some(2.0), ..Default::default() }),
+            ..Default::default()
+        };
+        let draws = settings.to_drawtexts(r"a\%bc");
+        assert_eq!(draws.len(), 4);
+        assert!(draws[0].starts_with("drawtext=text='a':"));
+        assert!(draws[1].starts_with(r"drawtext=text='a\\%':"));
+        // El ancho final (4 letras aproximadas a 30 píxeles) fija la posición
+        assert!(draws.iter().all(|d| d.contains(":x=(w-120)/2:")));
+        assert!(draws[0].ends_with(":enable='gte(t,1)*lt(t,1.5)'"));
+        assert!(draws[3].ends_with(":enable='gte(t,2.5)'"));
+    }
+
+    #[test]
+    fn test_long_typewriter_is_capped() {
+        let settings = TextSettings {
+            font: "sans-serif".to_string(),
+            size: 20,
+            color: "white".to_string(),
+            x: "10".to_string(),
+            y: "10".to_string(),
+            animation: Some(AnimationSettings { typewriter: Some(10.0), ..Default::default() }),
+            ..Default::default()
+        };
+        let text = "abcdefghij".repeat(20);
+        let draws = settings.to_drawtexts(&text);
+        // 200 letras en grupos de 4
+        assert_eq!(draws.len(), 50);
+        assert!(draws[0].starts_with("drawtext=text='abcd':"));
+        assert!(draws[0].ends_with(":enable='gte(t,0)*lt(t,0.2)'"));
+        assert!(draws[49].starts_with(&format!("drawtext=text='{text}':")));
+        assert!(draws[49].ends_with(":enable='gte(t,9.8)'"));
+    }
+
+    #[test]
+    fn test_visibility_window() {
+        let mut settings = TextSettings {
+            font: "sans-serif".to_string(),
+            size: 32,
+            color: "white".to_string(),
+            x: "10".to_string(),
+            y: "10".to_string(),
+            start: Some(5.0),
+            end: Some(12.5),
+            ..Default::default()
+        };
+        assert!(settings.to_drawtext("CTA").ends_with(":y=10:enable='between(t,5,12.5)'"));
+        assert!(settings.validate("texts[0]").is_empty());
+
+        settings.end = None;
+        assert!(settings.to_drawtext("CTA").ends_with(":enable='gte(t,5)'"));
+
+        settings.end = Some(2.0);
+        assert_eq!(settings.validate("texts[0]").len(), 1);
+    }
+
+    /// Separa un filtro en pares clave=valor como hace FFmpeg, deshaciendo los
+    /// niveles del filtergraph y de la opción.
+    fn parse_filter(filter: &str) -> Vec<(String, String)> {
+        let (graph, rest) = av_get_token(filter, "[],;");
+        assert!(rest.is_empty(), "el filtergraph cortó el filtro en: {rest}");
+        let mut args = graph.strip_prefix("drawtext=").expect("Filtro drawtext");
+        let mut pairs = Vec::new();
+        while !args.is_empty() {
+            let (pair, rest) = av_get_token(args, ":");
+            let (key, value) = pair.split_once('=').expect("Par clave=valor");
+            pairs.push((key.to_string(), value.to_string()));
+            args = rest.strip_prefix(':').unwrap_or(rest);
+        }
+        pairs
+    }
+
+    proptest! {
+        #[test]
+        fn prop_hostile_text_and_font(
+            title in "[^\u{0}]*",
+            font in "/[^\\u{0},]*\\.ttf",
+            color in "[a-z@.0-9,;:'\\\\]{1,12}",
+        ) {
+            let settings = TextSettings {
+                font: font.clone(),
+                size: 40,
+                color: color.clone(),
+                x: "if(gte(t,2),10,-w)".to_string(),
+                y: "h-th-10".to_string(),
+                start: Some(1.5),
+                ..Default::default()
+            };
+            let text = placeholder::expand_drawtext(
+                "{title}",
+                &Fields::from([("title".to_string(), title.clone())]),
+            );
+            let pairs = parse_filter(&settings.to_drawtext(&text));
+            let keys: Vec<&str> = pairs.iter().map(|(k, _)| k.as_str()).collect();
+            prop_assert_eq!(keys, ["text", "fontfile", "fontsize", "fontcolor", "x", "y", "enable"]);
+            prop_assert_eq!(drawtext_expand(&pairs[0].1), Some(title));
+            prop_assert_eq!(&pairs[1].1, &font);
+            prop_assert_eq!(&pairs[3].1, &color);
+            prop_assert_eq!(&pairs[4].1, "if(gte(t,2),10,-w)");
+            prop_assert_eq!(&pairs[6].1, "gte(t,1.5)");
+        }
+    }
 }
@@ -4,6 +4,7 @@ use serde::Deserialize;
 pub struct VideoSettings {
     pub width: u32,
     pub height: u32,
+    pub fps: Option<u32>,
 }
 
 #[cfg(test)]
@@ -15,9 +16,11 @@ mod tests {
         let settings = VideoSettings {
             width: 1920,
             height: 1080,
+            fps: Some(30),
         };
 
         assert_eq!(settings.width, 1920);
         assert_eq!(settings.height, 1080);
+        assert_eq!(settings.fps, Some(30));
     }
 }
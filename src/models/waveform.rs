@@ -14,10 +14,13 @@ pub struct WaveformSettings {
 }
 
 impl WaveformSettings {
-    pub fn to_filter_chain(&self) -> String {
+    /// Cadena de filtros de la onda. Sin `rate`, la onda se genera a los `fps` del
+    /// video para que cada fotograma tenga la suya.
+    pub fn to_filter_chain(&self, fps: u32) -> String {
+        let actual_rate = self.rate.unwrap_or(fps as i32);
         // 1. Obtenemos el filtro base (del estilo o del primer paso del pipeline)
         let base_filter = if let Some(style) = &self.style {
-            style.get_filter(self.width, self.height, self.color.as_deref(), self.rate)
+            style.get_filter(self.width, self.height, self.color.as_deref(), actual_rate)
         } else {
             // Si no hay estilo, asumimos que el primer filtro del pipeline usa {w} y {h}
             let first = self
@@ -27,7 +30,6 @@ impl WaveformSettings {
                 .cloned()
                 .unwrap_or_else(|| "showwaves=s={w}x{h}:rate={r}".to_string());
 
-            let actual_rate = self.rate.unwrap_or(60);
             let mut replaced = first
                 .replace("{w}", &self.width.to_string())
                 .replace("{h}", &self.height.to_string());
@@ -74,7 +76,7 @@ mod tests {
             pipeline: None,
         };
         let expected = "showwaves=s=800x300:mode=line:colors=cyan:rate=60,format=rgba,colorkey=0x000000:0.1:0.1";
-        assert_eq!(settings.to_filter_chain(), expected);
+        assert_eq!(settings.to_filter_chain(60), expected);
     }
     #[test]
     fn test_to_filter_chain_with_style_and_color() {
@@ -89,7 +91,7 @@ mod tests {
             pipeline: None,
         };
         let expected = "showwaves=s=800x300:mode=line:colors=red:rate=60,format=rgba,colorkey=0x000000:0.1:0.1";
-        assert_eq!(settings.to_filter_chain(), expected);
+        assert_eq!(settings.to_filter_chain(60), expected);
     }
     #[test]
     fn test_to_filter_chain_with_style_and_pipeline() {
@@ -107,7 +109,7 @@ mod tests {
             ]),
         };
         let expected = "showwaves=s=800x300:mode=line:colors=cyan:rate=60,format=rgba,colorkey=0x000000:0.1:0.1,aformat=channel_layouts=mono,compand";
-        assert_eq!(settings.to_filter_chain(), expected);
+        assert_eq!(settings.to_filter_chain(60), expected);
     }
     #[test]
     fn test_to_filter_chain_with_pipeline_only() {
@@ -125,7 +127,7 @@ mod tests {
             ]),
         };
         let expected = "showwaves=s=800x300:colors=red,compand";
-        assert_eq!(settings.to_filter_chain(), expected);
+        assert_eq!(settings.to_filter_chain(60), expected);
     }
     #[test]
     fn test_to_filter_chain_with_pipeline_and_color() {
@@ -143,7 +145,7 @@ mod tests {
             ]),
         };
         let expected = "showwaves=s=800x300:colors=blue,compand";
-        assert_eq!(settings.to_filter_chain(), expected);
+        assert_eq!(settings.to_filter_chain(60), expected);
     }
 
     #[test]
@@ -159,5 +161,5 @@ mod tests {
             pipeline: None,
         };
         let expected = "showwaves=s=800x300:rate=60";
-        assert_eq!(settings.to_filter_chain(), expected);
+        assert_eq!(settings.to_filter_chain(60), expected);
     }}
@@ -0,0 +1,162 @@
+use chrono::{
+    NaiveDate,
+    format::{Item, StrftimeItems},
+};
+use crate::escape;
+use regex::Regex;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+/// Valores disponibles para los textos: metadatos, `show.vars` y `--var`.
+pub type Fields = BTreeMap<String, String>;
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{|\}\}|\{([A-Za-z_][A-Za-z0-9_]*)(?::([^{}]*))?\}").expect("Regex válida")
+});
+
+/// Sustituye cada `{campo}` por su valor. Admite especificadores:
+///
+/// * `{episode:03}` rellena números con ceros.
+/// * `{date:%d/%m/%Y}` formatea fechas `AAAA-MM-DD` con la sintaxis de `strftime`.
+///
+/// `{{` y `}}` escriben llaves literales. Los nombres que no están en `fields` se
+/// dejan tal cual para no romper textos que usan llaves.
+pub fn expand(text: &str, fields: &Fields) -> String {
+    expand_with(text, fields, str::to_string, str::to_string)
+}
+
+/// Como [`expand`], pero para rutas: los valores no pueden crear directorios nuevos.
+pub fn expand_path(text: &str, fields: &Fields) -> String {
+    expand_with(text, fields, str::to_string, |value| value.replace(['/', '\\'], "-"))
+}
+
+/// Como [`expand`], pero con el escapado de drawtext: los valores se dibujan tal
+/// cual y el texto fijo conserva las expansiones `%{...}` de FFmpeg.
+pub fn expand_drawtext(text: &str, fields: &Fields) -> String {
+    expand_with(text, fields, escape::drawtext_literal, escape::drawtext_value)
+}
+
+/// Convierte `clave=valor` de `--var` en un par.
+pub fn parse_var(value: &str) -> Result<(String, String), String> {
+    let (key, val) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Variable no válida: '{value}' (usa clave=valor)"))?;
+    let key = key.trim();
+    let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("Nombre de variable no válido: '{key}'"));
+    }
+    Ok((key.to_string(), val.to_string()))
+}
+
+fn expand_with(
+    text: &str,
+    fields: &Fields,
+    literal: impl Fn(&str) -> String,
+    value: impl Fn(&str) -> String,
+) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Texto fijo pendiente; se escapa de una vez para no partir un `%{...}`
+    let mut pending = String::new();
+    let mut last = 0;
+    for caps in PLACEHOLDER.captures_iter(text) {
+        let whole = caps.get(0).expect("Grupo 0 siempre presente");
+        pending.push_str(&text[last..whole.start()]);
+        last = whole.end();
+        match whole.as_str() {
+            "{{" => pending.push('{'),
+            "}}" => pending.push('}'),
+            raw => match fields.get(&caps[1]) {
+                Some(field) => {
+                    let field = match caps.get(2) {
+                        Some(spec) => apply_spec(field, spec.as_str()),
+                        None => field.clone(),
+                    };
+                    out.push_str(&literal(&pending));
+                    pending.clear();
+                    out.push_str(&value(&field));
+                }
+                None => pending.push_str(raw),
+            },
+        }
+    }
+    pending.push_str(&text[last..]);
+    out.push_str(&literal(&pending));
+    out
+}
+
+/// Aplica el especificador; si no encaja con el valor, lo devuelve sin cambios.
+fn apply_spec(value: &str, spec: &str) -> String {
+    if spec.contains('%') {
+        return format_date(value, spec).unwrap_or_else(|| value.to_string());
+    }
+    if let Some(width) = spec.strip_prefix('0').and_then(|w| w.parse::<usize>().ok())
+        && let Ok(number) = value.trim().parse::<i64>()
+    {
+        return format!("{number:0width$}");
+    }
+    value.to_string()
+}
+
+fn format_date(value: &str, spec: &str) -> Option<String> {
+    let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+    let items: Vec<Item> = StrftimeItems::new(spec).collect();
+    if items.contains(&Item::Error) {
+        return None;
+    }
+    Some(date.format_with_items(items.into_iter()).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> Fields {
+        [("episode", "7"), ("date", "2026-10-18"), ("album", "AC/DC"), ("genre", "")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand("Episodio {episode} · {date}", &fields()),
+            "Episodio 7 · 2026-10-18"
+        );
+        assert_eq!(expand("[{genre}] {unknown} {}", &fields()), "[] {unknown} {}");
+        assert_eq!(expand("{{episode}} {{", &fields()), "{episode} {");
+    }
+
+    #[test]
+    fn test_expand_specifiers() {
+        assert_eq!(expand("Ep. {episode:03}", &fields()), "Ep. 007");
+        assert_eq!(expand("{date:%d/%m/%Y}", &fields()), "18/10/2026");
+        assert_eq!(expand("{album:03} {date:%Q}", &fields()), "AC/DC 2026-10-18");
+    }
+
+    #[test]
+    fn test_expand_path() {
+        assert_eq!(expand_path("out/{album}-{episode:02}.mp4", &fields()), "out/AC-DC-07.mp4");
+    }
+
+    #[test]
+    fn test_expand_drawtext() {
+        let mut fields = fields();
+        fields.insert("title".to_string(), "100% C:\\temp %{pts}".to_string());
+        assert_eq!(
+            expand_drawtext("{title} · 50% · %{pts:hms}", &fields),
+            r"100\% C:\\temp \%{pts} · 50\% · %{pts:hms}"
+        );
+    }
+
+    #[test]
+    fn test_parse_var() {
+        assert_eq!(
+            parse_var("web=https://atareao.es/?a=1").unwrap(),
+            ("web".to_string(), "https://atareao.es/?a=1".to_string())
+        );
+        assert!(parse_var("sin_igual").is_err());
+        assert!(parse_var("1abc=x").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+/// Duración por defecto de `--preview` cuando no se indica `--duration`.
+pub const PREVIEW_SECONDS: f64 = 5.0;
+
+/// Ventana de tiempo y calidad con la que se renderiza una previsualización.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Preview {
+    /// Segundo del audio desde el que se empieza a renderizar.
+    pub start: Option<f64>,
+    /// Segundos a renderizar a partir de `start`.
+    pub duration: Option<f64>,
+    /// Codifica a mitad de resolución con el preset rápido. La composición (fondo,
+    /// onda y textos) se sigue haciendo a tamaño completo: solo se abarata la
+    /// codificación.
+    pub draft: bool,
+    /// Instante del que se extrae un único fotograma PNG.
+    pub frame: Option<f64>,
+}
+
+impl Preview {
+    /// Segundo del audio en el que empieza el video: el del fotograma suelto o el
+    /// de `start`.
+    pub fn seek(&self) -> f64 {
+        self.frame.or(self.start).unwrap_or(0.0)
+    }
+
+    /// Argumentos que se colocan justo antes de `-i <audio>` para buscar el inicio.
+    pub fn input_args(&self) -> Vec<String> {
+        match self.frame.or(self.start) {
+            Some(seek) if seek > 0.0 => vec!["-ss".to_string(), format_seconds(seek)],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Añade al grafo el escalado del modo borrador y devuelve la etiqueta final.
+    /// El escalado va al final para que las posiciones y tamaños de la plantilla,
+    /// que están en píxeles del video completo, no cambien.
+    pub fn apply_to_filter(&self, filter: String, width: u32, height: u32) -> (String, &'static str) {
+        if self.draft {
+            // libx264 y yuv420p necesitan dimensiones pares
+            let w = (width / 2) & !1;
+            let h = (height / 2) & !1;
+            (format!("{filter};[outv]scale={w}:{h}[draft]"), "[draft]")
+        } else {
+            (filter, "[outv]")
+        }
+    }
+
+    /// Segundos de audio que se van a procesar, si se pueden conocer de antemano.
+    pub fn window_seconds(&self, total: f64) -> f64 {
+        let remaining = (total - self.start.unwrap_or(0.0)).max(0.0);
+        match self.duration {
+            Some(d) => d.min(remaining),
+            None => remaining,
+        }
+    }
+}
+
+/// Convierte "90", "1:30", "00:01:30.5" o "90.5" en segundos.
+pub fn parse_timestamp(value: &str) -> Result<f64, String> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() > 3 || parts.iter().any(|p| p.is_empty()) {
+        return Err(format!("Marca de tiempo no válida: '{value}'"));
+    }
+
+    let mut seconds = 0.0;
+    for part in parts {
+        let n: f64 = part
+            .parse()
+            .map_err(|_| format!("Marca de tiempo no válida: '{value}'"))?;
+        if n < 0.0 {
+            return Err(format!("Marca de tiempo no válida: '{value}'"));
+        }
+        seconds = seconds * 60.0 + n;
+    }
+    Ok(seconds)
+}
+
+fn format_seconds(seconds: f64) -> String {
+    format!("{seconds:.3}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("90").unwrap(), 90.0);
+        assert_eq!(parse_timestamp("1:30").unwrap(), 90.0);
+        assert_eq!(parse_timestamp("00:01:30.5").unwrap(), 90.5);
+        assert!(parse_timestamp("1:2:3:4").is_err());
+        assert!(parse_timestamp("abc").is_err());
+        assert!(parse_timestamp("1::2").is_err());
+    }
+
+    #[test]
+    fn test_input_args_seek() {
+        let preview = Preview {
+            start: Some(12.5),
+            ..Default::default()
+        };
+        assert_eq!(preview.input_args(), vec!["-ss", "12.500"]);
+
+        let frame = Preview {
+            frame: Some(3.0),
+            ..Default::default()
+        };
+        assert_eq!(frame.input_args(), vec!["-ss", "3.000"]);
+        assert!(Preview::default().input_args().is_empty());
+    }
+
+    #[test]
+    fn test_draft_scales_output() {
+        let preview = Preview {
+            draft: true,
+            ..Default::default()
+        };
+        let (filter, label) = preview.apply_to_filter("[bg]null[outv]".to_string(), 1920, 1080);
+        assert_eq!(filter, "[bg]null[outv];[outv]scale=960:540[draft]");
+        assert_eq!(label, "[draft]");
+
+        let (filter, label) = Preview::default().apply_to_filter("[bg]null[outv]".to_string(), 1920, 1080);
+        assert_eq!(filter, "[bg]null[outv]");
+        assert_eq!(label, "[outv]");
+    }
+
+    #[test]
+    fn test_window_seconds() {
+        let preview = Preview {
+            start: Some(10.0),
+            duration: Some(5.0),
+            ..Default::default()
+        };
+        assert_eq!(preview.window_seconds(60.0), 5.0);
+        assert_eq!(preview.window_seconds(12.0), 2.0);
+        assert_eq!(Preview::default().window_seconds(60.0), 60.0);
+    }
+}
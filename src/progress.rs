@@ -0,0 +1,192 @@
+use regex::Regex;
+use serde::Serialize;
+
+/// Estado del renderizado según el protocolo `-progress` de FFmpeg.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProgressUpdate {
+    /// Segundos de video ya renderizados.
+    pub seconds: f64,
+    /// Segundos totales a renderizar (0 si aún no se conocen).
+    pub total: f64,
+    /// Porcentaje completado (0-100).
+    pub percent: f64,
+    pub frame: Option<u64>,
+    pub fps: Option<f64>,
+    /// Velocidad respecto al tiempo real (2.0 = el doble de rápido).
+    pub speed: Option<f64>,
+    /// Bytes escritos en el archivo de salida.
+    pub total_size: Option<u64>,
+    /// Segundos que faltan según la velocidad actual.
+    pub eta: Option<f64>,
+    /// FFmpeg envió `progress=end`.
+    pub finished: bool,
+}
+
+/// Lee las líneas de stderr de FFmpeg y agrupa los bloques `clave=valor` de `-progress`.
+///
+/// La duración total se toma de la línea `Duration:` de la entrada de audio, para no
+/// confundirla con la de la imagen de fondo.
+pub struct ProgressParser {
+    audio_input: usize,
+    current_input: Option<usize>,
+    re_input: Regex,
+    re_duration: Regex,
+    total: f64,
+    block: ProgressUpdate,
+}
+
+impl ProgressParser {
+    /// `audio_input` es el índice de la entrada de audio en la línea de comandos.
+    pub fn new(audio_input: usize) -> Self {
+        Self {
+            audio_input,
+            current_input: None,
+            re_input: Regex::new(r"^Input #(\d+),").expect("Regex válida"),
+            re_duration: Regex::new(r"Duration: (\d+):(\d{2}):(\d{2}(?:\.\d+)?)")
+                .expect("Regex válida"),
+            total: 0.0,
+            block: ProgressUpdate::default(),
+        }
+    }
+
+    /// Fija la duración total, por ejemplo cuando se renderiza solo una ventana.
+    pub fn set_total(&mut self, total: f64) {
+        self.total = total;
+    }
+
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// Procesa una línea. Devuelve la duración total la primera vez que se conoce.
+    pub fn parse_duration(&mut self, line: &str) -> Option<f64> {
+        if let Some(caps) = self.re_input.captures(line) {
+            self.current_input = caps[1].parse().ok();
+            return None;
+        }
+        if self.total > 0.0 || self.current_input != Some(self.audio_input) {
+            return None;
+        }
+        let caps = self.re_duration.captures(line)?;
+        let h: f64 = caps[1].parse().ok()?;
+        let m: f64 = caps[2].parse().ok()?;
+        let s: f64 = caps[3].parse().ok()?;
+        Some(h * 3600.0 + m * 60.0 + s)
+    }
+
+    /// Procesa una línea `clave=valor`. Devuelve el bloque completo al llegar a `progress=`.
+    pub fn parse_progress(&mut self, line: &str) -> Option<ProgressUpdate> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+        // La línea de estadísticas (`frame=  750 fps= 30 ... speed=1x`) no es un par
+        // `clave=valor` y no debe pisar los valores del bloque
+        if value.contains('=') {
+            return None;
+        }
+        match key {
+            "frame" => self.block.frame = value.parse().ok(),
+            "fps" => self.block.fps = value.parse().ok(),
+            "total_size" => self.block.total_size = value.parse().ok(),
+            "out_time_us" => {
+                if let Ok(us) = value.parse::<i64>() {
+                    self.block.seconds = us.max(0) as f64 / 1_000_000.0;
+                }
+            }
+            "speed" => self.block.speed = value.trim_end_matches('x').trim().parse().ok(),
+            "progress" => {
+                let mut update = std::mem::take(&mut self.block);
+                update.finished = value == "end";
+                update.total = self.total;
+                if self.total > 0.0 {
+                    update.seconds = update.seconds.min(self.total);
+                    update.percent = if update.finished {
+                        100.0
+                    } else {
+                        update.seconds / self.total * 100.0
+                    };
+                    update.eta = update
+                        .speed
+                        .filter(|speed| *speed > 0.0)
+                        .map(|speed| (self.total - update.seconds) / speed);
+                }
+                return Some(update);
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &[&str] = &[
+        "Input #0, png_pipe, from 'background.png':",
+        "  Duration: 00:00:00.04, start: 0.000000, bitrate: N/A",
+        "Input #1, mp3, from 'in.mp3':",
+        "  Duration: 00:01:40.50, start: 0.025057, bitrate: 128 kb/s",
+    ];
+
+    #[test]
+    fn test_duration_from_audio_input() {
+        let mut parser = ProgressParser::new(1);
+        let durations: Vec<f64> = HEADER
+            .iter()
+            .filter_map(|line| parser.parse_duration(line))
+            .collect();
+        assert_eq!(durations, vec![100.5]);
+    }
+
+    #[test]
+    fn test_progress_block() {
+        let mut parser = ProgressParser::new(1);
+        parser.set_total(100.0);
+        let block = [
+            "frame=750",
+            "fps=250.00",
+            "stream_0_0_q=28.0",
+            "bitrate= 512.0kbits/s",
+            "total_size=1600000",
+            "out_time_us=25000000",
+            "out_time_ms=25000000",
+            "out_time=00:00:25.000000",
+            "dup_frames=0",
+            "drop_frames=0",
+            "speed=2.5x",
+            // Línea de estadísticas que FFmpeg escribe con `\r` si no se usa -nostats
+            "frame=  750 fps= 30 q=28.0 size=    1536kB time=00:00:25.00 bitrate= 503.3kbits/s speed=1x",
+        ];
+        for line in block {
+            assert!(parser.parse_progress(line).is_none());
+        }
+        let update = parser.parse_progress("progress=continue").unwrap();
+        assert_eq!(
+            update,
+            ProgressUpdate {
+                seconds: 25.0,
+                total: 100.0,
+                percent: 25.0,
+                frame: Some(750),
+                fps: Some(250.0),
+                speed: Some(2.5),
+                total_size: Some(1_600_000),
+                eta: Some(30.0),
+                finished: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_progress_end_and_unknown_values() {
+        let mut parser = ProgressParser::new(1);
+        parser.set_total(10.0);
+        parser.parse_progress("out_time_us=N/A");
+        parser.parse_progress("speed=N/A");
+        let update = parser.parse_progress("progress=end").unwrap();
+        assert!(update.finished);
+        assert_eq!(update.percent, 100.0);
+        assert_eq!(update.speed, None);
+        assert_eq!(update.eta, None);
+    }
+}
@@ -0,0 +1,1080 @@
+use crate::backend::{FfmpegBackend, MediaBackend};
+use crate::captions::Cue;
+use crate::error::{AudiowaveError, Result};
+use crate::format::{self, OutputFormat};
+use crate::models::{background, template::Template};
+use crate::placeholder::Fields;
+use crate::preview::Preview;
+use crate::progress::{ProgressParser, ProgressUpdate};
+use log::debug;
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io::Write,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tempfile::TempPath;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Líneas de log de FFmpeg que se guardan para informar de un fallo.
+const ERROR_LOG_LINES: usize = 15;
+
+/// Índice de la entrada de audio en la línea de comandos de FFmpeg.
+const AUDIO_INPUT: usize = 1;
+
+/// Eventos que emite un renderizado en curso.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RenderEvent {
+    /// Segundos de audio que se van a renderizar.
+    Duration { seconds: f64 },
+    /// Bloque de progreso de FFmpeg.
+    Progress(ProgressUpdate),
+}
+
+/// Todo lo necesario para renderizar un video. Se construye con [`RenderJob::builder`].
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    template: Template,
+    input: PathBuf,
+    output: PathBuf,
+    /// Valores de los campos de texto, incluidos `title` y `subtitle`.
+    fields: Fields,
+    /// Carátula del audio; se borra cuando se suelta el trabajo.
+    cover: Option<Arc<TempPath>>,
+    background: Option<PathBuf>,
+    preview: Preview,
+    fps: Option<u32>,
+    format: Option<OutputFormat>,
+    transparent: bool,
+    /// Guion ASS de los subtítulos, ya desplazado al punto de búsqueda. Se borra
+    /// cuando se suelta el trabajo.
+    captions: Option<Arc<TempPath>>,
+}
+
+impl RenderJob {
+    pub fn builder(template: Template) -> RenderJobBuilder {
+        RenderJobBuilder {
+            template,
+            input: None,
+            output: None,
+            fields: Fields::new(),
+            cover: None,
+            background: None,
+            preview: Preview::default(),
+            fps: None,
+            format: None,
+            transparent: false,
+            captions: Vec::new(),
+        }
+    }
+
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+
+    /// Formato de salida; `None` si la extensión no es conocida y no se indicó ninguno.
+    pub fn format(&self) -> Option<OutputFormat> {
+        self.format
+    }
+
+    /// Argumentos de FFmpeg (sin el nombre del programa) para este trabajo.
+    pub fn ffmpeg_args(&self) -> Vec<String> {
+        // Un fotograma suelto ignora el formato; sin extensión conocida se usan los
+        // códecs de MKV y FFmpeg elige el muxer
+        let frame = self.preview.frame.is_some();
+        let profile = self.format.unwrap_or(OutputFormat::Mkv);
+        let fps = self.fps.unwrap_or_else(|| self.template.fps());
+
+        // El audio empieza en el punto de búsqueda: las capas se desplazan
+        let seek = self.preview.seek();
+        let captions = self.captions.as_deref().map(|path| path.as_ref());
+        // Archivo de fondo: la carátula, el del trabajo (fondo del programa) o el de
+        // la plantilla; sin ninguno, el fondo lo genera FFmpeg
+        let file = self
+            .cover
+            .as_deref()
+            .map(|path| path.as_ref())
+            .or(self.background.as_deref())
+            .map(|p| p.to_string_lossy().into_owned())
+            .or_else(|| Some(self.template.background.path.clone()).filter(|p| !p.is_empty()));
+        let filter = if self.transparent {
+            self.template.build_transparent_filter_complex(&self.fields, captions, seek)
+        } else if file.as_deref().is_some_and(background::is_video) {
+            self.template.build_video_filter_complex(&self.fields, captions, seek, fps)
+        } else if file.is_some() {
+            self.template.build_filter_complex(&self.fields, captions, seek)
+        } else {
+            self.template.build_generated_filter_complex(&self.fields, captions, seek)
+        };
+        let (filter, video_label) = self.preview.apply_to_filter(
+            filter,
+            self.template.video.width,
+            self.template.video.height,
+        );
+        let (filter, video_label) = if frame {
+            (filter, video_label)
+        } else {
+            profile.apply_to_filter(filter, video_label)
+        };
+
+        let mut args: Vec<String> = vec![
+            "-y".into(),
+            "-nostats".into(),
+            "-progress".into(),
+            "pipe:2".into(),
+            "-v".into(),
+            "info".into(),
+        ];
+        if self.transparent {
+            args.extend(["-f".into(), "lavfi".into(), "-i".into(), self.template.transparent_canvas(fps)]);
+        } else if let Some(file) = file {
+            args.extend(background::input_args(&file, fps));
+        } else {
+            let (width, height) = (self.template.video.width, self.template.video.height);
+            let source = self.template.background.to_source(width, height, fps);
+            args.extend(["-f".into(), "lavfi".into(), "-i".into(), source]);
+        }
+        args.extend(self.preview.input_args());
+        args.extend([
+            "-i".into(),
+            self.input.to_string_lossy().into_owned(),
+            "-filter_complex".into(),
+            filter,
+            "-map".into(),
+            video_label.into(),
+        ]);
+
+        if frame {
+            args.extend(["-frames:v".into(), "1".into(), "-update".into(), "1".into()]);
+        } else {
+            if profile.has_audio() {
+                args.extend(["-map".into(), "1:a".into()]);
+            }
+            if let Some(duration) = self.preview.duration {
+                args.extend(["-t".into(), format!("{duration:.3}")]);
+            }
+            args.extend(["-r".into(), fps.to_string()]);
+            match profile.alpha_video_args(self.preview.draft) {
+                // El perfil de la plantilla es para el video completo: aquí solo se usa su audio
+                Some(video) if self.transparent => {
+                    args.extend(video);
+                    args.extend(self.template.encoding.audio_args(profile));
+                }
+                _ => args.extend(self.template.encoding.to_args(profile, self.preview.draft)),
+            }
+            if let Some(format) = self.format {
+                args.extend(format.muxer_args());
+            }
+            args.push("-shortest".into());
+        }
+        args.push(self.output.to_string_lossy().into_owned());
+        args
+    }
+}
+
+pub struct RenderJobBuilder {
+    template: Template,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    /// Valores de los campos de texto, incluidos `title` y `subtitle`.
+    fields: Fields,
+    cover: Option<Arc<TempPath>>,
+    background: Option<PathBuf>,
+    preview: Preview,
+    fps: Option<u32>,
+    format: Option<OutputFormat>,
+    transparent: bool,
+    /// Subtítulos en tiempo del audio.
+    captions: Vec<Cue>,
+}
+
+impl RenderJobBuilder {
+    /// Audio de entrada.
+    pub fn input(mut self, input: impl Into<PathBuf>) -> Self {
+        self.input = Some(input.into());
+        self
+    }
+
+    /// Archivo de salida.
+    pub fn output(mut self, output: impl Into<PathBuf>) -> Self {
+        self.output = Some(output.into());
+        self
+    }
+
+    /// Valor de `{title}`, el texto por defecto de la capa de título.
+    pub fn title(self, title: impl Into<String>) -> Self {
+        self.var("title", title)
+    }
+
+    /// Valor de `{subtitle}`, el texto por defecto de la capa de subtítulo.
+    pub fn subtitle(self, subtitle: impl Into<String>) -> Self {
+        self.var("subtitle", subtitle)
+    }
+
+    /// Añade un campo para los textos de la plantilla.
+    pub fn var(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.insert(key.into(), value.into());
+        self
+    }
+
+    /// Añade varios campos (por ejemplo, los de [`crate::AudioMetadata::fields`]).
+    pub fn fields(mut self, fields: Fields) -> Self {
+        self.fields.extend(fields);
+        self
+    }
+
+    /// Carátula del audio (ver [`crate::AudioMetadata::cover`]). Tiene prioridad
+    /// sobre `background` y el trabajo conserva el archivo temporal hasta terminar.
+    pub fn cover(mut self, cover: Option<Arc<TempPath>>) -> Self {
+        self.cover = cover;
+        self
+    }
+
+    /// Imagen de fondo que sustituye a la de la plantilla.
+    pub fn background(mut self, background: Option<PathBuf>) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn preview(mut self, preview: Preview) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    /// Frame rate de salida; sin él se usa el de la plantilla.
+    pub fn fps(mut self, fps: impl Into<Option<u32>>) -> Self {
+        self.fps = fps.into();
+        self
+    }
+
+    /// Formato de salida; sin él se deduce de la extensión del archivo.
+    pub fn format(mut self, format: impl Into<Option<OutputFormat>>) -> Self {
+        self.format = format.into();
+        self
+    }
+
+    /// Renderiza solo la onda y los textos sobre un fondo transparente.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Subtítulos que se queman en el video (ver [`crate::captions::load`]).
+    pub fn captions(mut self, captions: Vec<Cue>) -> Self {
+        self.captions = captions;
+        self
+    }
+
+    pub fn build(self) -> Result<RenderJob> {
+        let input = self
+            .input
+            .ok_or_else(|| AudiowaveError::InvalidJob("falta el audio de entrada".to_string()))?;
+        let output = self
+            .output
+            .ok_or_else(|| AudiowaveError::InvalidJob("falta el archivo de salida".to_string()))?;
+
+        // Un fotograma suelto siempre es PNG: el formato no se aplica
+        let format = if self.preview.frame.is_some() {
+            None
+        } else {
+            format::resolve(self.format, &output)?
+        };
+        if let Some(format) = format {
+            let encoding = &self.template.encoding;
+            if self.transparent {
+                if format.alpha_video_args(false).is_none() {
+                    return Err(AudiowaveError::IncompatibleFormat(format!(
+                        "{format} no admite transparencia (usa mov, webm o png)"
+                    )));
+                }
+                format.check_codecs(None, encoding.audio_codec.as_deref())?;
+            } else {
+                format.check_codecs(encoding.video_codec.as_deref(), encoding.audio_codec.as_deref())?;
+            }
+            if !format.has_audio() && self.preview.duration.is_none() {
+                return Err(AudiowaveError::IncompatibleFormat(format!(
+                    "{format} no lleva audio: indica --duration o --preview para acotarlo"
+                )));
+            }
+        } else if self.transparent && self.preview.frame.is_none() {
+            return Err(AudiowaveError::IncompatibleFormat(
+                "la transparencia necesita un formato mov, webm o png".to_string(),
+            ));
+        }
+        let output = match format {
+            Some(OutputFormat::Png) => format::sequence_pattern(&output),
+            _ => output,
+        };
+        let mut template = self.template;
+        template.resolve_fonts().map_err(AudiowaveError::FontNotFound)?;
+        // El fps del trabajo manda sobre el de la plantilla, también para la onda
+        if let Some(fps) = self.fps {
+            template.encoding.fps = Some(fps);
+        }
+        // Los subtítulos van en un archivo aparte: como filtros no caben en un argumento
+        let captions = if self.captions.is_empty() {
+            None
+        } else {
+            let script = template.captions_script(&self.captions, self.preview.seek());
+            Some(Arc::new(write_captions(&script)?))
+        };
+
+        Ok(RenderJob {
+            template,
+            input,
+            output,
+            fields: self.fields,
+            cover: self.cover,
+            background: self.background,
+            preview: self.preview,
+            fps: self.fps,
+            format,
+            transparent: self.transparent,
+            captions,
+        })
+    }
+}
+
+/// Guarda el guion de subtítulos en un archivo temporal.
+fn write_captions(script: &str) -> Result<TempPath> {
+    let mut file = tempfile::Builder::new()
+        .prefix("audiowave_captions_")
+        .suffix(".ass")
+        .tempfile()?;
+    file.write_all(script.as_bytes())?;
+    Ok(file.into_temp_path())
+}
+
+/// Lanza los trabajos de renderizado sobre un [`MediaBackend`] (FFmpeg por defecto).
+#[derive(Debug, Clone, Default)]
+pub struct Renderer<B: MediaBackend = FfmpegBackend> {
+    backend: Arc<B>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: MediaBackend> Renderer<B> {
+    pub fn with_backend(backend: B) -> Self {
+        Self {
+            backend: Arc::new(backend),
+        }
+    }
+
+    /// Arranca el renderizado en segundo plano. El [`RenderHandle`] devuelto emite
+    /// los eventos de progreso y se resuelve con la ruta del video generado.
+    pub fn render(&self, job: RenderJob) -> RenderHandle {
+        let (tx, events) = mpsc::unbounded_channel();
+        let backend = Arc::clone(&self.backend);
+        let task = tokio::spawn(async move { run_job(backend.as_ref(), job, tx).await });
+        RenderHandle { events, task }
+    }
+}
+
+/// Renderizado en curso. Es un `Future` que se resuelve con el resultado final.
+/// Soltarlo cancela el trabajo y termina el proceso de FFmpeg.
+pub struct RenderHandle {
+    events: mpsc::UnboundedReceiver<RenderEvent>,
+    task: JoinHandle<Result<PathBuf>>,
+}
+
+impl RenderHandle {
+    /// Siguiente evento de progreso, o `None` cuando FFmpeg ha terminado.
+    pub async fn next_event(&mut self) -> Option<RenderEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for RenderHandle {
+    fn drop(&mut self) {
+        // Un `JoinHandle` soltado no para la tarea: sin esto FFmpeg seguiría en marcha
+        self.task.abort();
+    }
+}
+
+impl Future for RenderHandle {
+    type Output = Result<PathBuf>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.task).poll(cx).map(|joined| {
+            joined.unwrap_or_else(|e| match e.try_into_panic() {
+                // Un pánico en la tarea es un error interno, no un problema de FFmpeg
+                Ok(panic) => std::panic::resume_unwind(panic),
+                Err(e) => Err(AudiowaveError::Io(std::io::Error::other(e))),
+            })
+        })
+    }
+}
+
+async fn run_job<B: MediaBackend>(
+    backend: &B,
+    job: RenderJob,
+    events: mpsc::UnboundedSender<RenderEvent>,
+) -> Result<PathBuf> {
+    let (line_tx, mut line_rx) = mpsc::unbounded_channel::<String>();
+    let run = backend.run(job.ffmpeg_args(), line_tx);
+
+    let parse = async {
+        let mut parser = ProgressParser::new(AUDIO_INPUT);
+        // Búfer para guardar las últimas líneas de log en caso de error
+        let mut error_logs: VecDeque<String> = VecDeque::with_capacity(ERROR_LOG_LINES);
+
+        while let Some(line) = line_rx.recv().await {
+            debug!("Log FFmpeg: {}", line);
+            if let Some(duration) = parser.parse_duration(&line) {
+                let seconds = job.preview.window_seconds(duration);
+                parser.set_total(seconds);
+                debug!("⏱ Duración total: {} segundos", seconds);
+                let _ = events.send(RenderEvent::Duration { seconds });
+            }
+            if let Some(update) = parser.parse_progress(&line) {
+                debug!("Progreso: {:.0}%", update.percent);
+                let _ = events.send(RenderEvent::Progress(update));
+            }
+            // Las líneas `clave=valor` de -progress no aportan nada al log de errores
+            if line.contains('=') && !line.contains(' ') {
+                continue;
+            }
+            if error_logs.len() >= ERROR_LOG_LINES {
+                error_logs.pop_front();
+            }
+            error_logs.push_back(line);
+        }
+        error_logs
+    };
+
+    let (status, error_logs) = tokio::join!(run, parse);
+    let status = status?;
+    if status.success {
+        Ok(job.output)
+    } else {
+        Err(AudiowaveError::FfmpegFailed {
+            code: status.code,
+            log: error_logs.into(),
+        })
+    }
+}
+
+/// Nombre de archivo a partir del título: minúsculas y solo caracteres alfanuméricos.
+pub fn output_name_from_title(title: &str, extension: &str) -> String {
+    let safe_title = title
+        .to_lowercase()
+        .replace(|c: char| !c.is_alphanumeric(), "_")
+        .replace("__", "_");
+    format!("{}.{}", safe_title.trim_matches('_'), extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::ExitStatus;
+    use std::sync::Mutex;
+    use crate::models::{
+        background::{BackgroundSettings, GeneratedBackground},
+        grading::Grading, style::WaveformStyle, video::VideoSettings,
+        animation::{AnimationSettings, Edge, SlideSettings}, text::TextSettings,
+        waveform::WaveformSettings,
+    };
+
+    fn sample_template() -> Template {
+        Template {
+            video: VideoSettings {
+                width: 1280,
+                height: 720,
+                fps: None,
+            },
+            background: BackgroundSettings {
+                path: "background.png".to_string(),
+                mode: "stretch".to_string(),
+                ..Default::default()
+            },
+            waveform: WaveformSettings {
+                style: Some(WaveformStyle::ClassicLine),
+                width: 800,
+                height: 300,
+                x: "100".to_string(),
+                y: "200".to_string(),
+                color: None,
+                rate: None,
+                pipeline: None,
+            },
+            title: None,
+            subtitle: None,
+            texts: Vec::new(),
+            encoding: Default::default(),
+            show: Default::default(),
+            captions: None,
+            post: None,
+        }
+    }
+
+    #[test]
+    fn test_builder_requires_input_and_output() {
+        let result = RenderJob::builder(sample_template()).output("out.mkv").build();
+        assert!(matches!(result, Err(AudiowaveError::InvalidJob(_))));
+        let result = RenderJob::builder(sample_template()).input("in.mp3").build();
+        assert!(matches!(result, Err(AudiowaveError::InvalidJob(_))));
+    }
+
+    #[test]
+    fn test_ffmpeg_args() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mkv")
+            .background(Some(PathBuf::from("cover.jpg")))
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert_eq!(
+            args[..12],
+            ["-y", "-nostats", "-progress", "pipe:2", "-v", "info", "-loop", "1", "-framerate", "30", "-i", "cover.jpg"]
+        );
+        assert_eq!(args[12..14], ["-i", "in.mp3"]);
+        assert_eq!(args[16..18], ["-map", "[outv]"]);
+        assert!(args.windows(2).any(|w| w == ["-r", "30"]));
+        assert!(args.windows(2).any(|w| w == ["-c:v", "libx264"]));
+        assert_eq!(args.last().unwrap(), "out.mkv");
+    }
+
+    #[test]
+    fn test_job_owns_the_cover() {
+        let cover = Arc::new(tempfile::Builder::new().suffix(".png").tempfile().unwrap().into_temp_path());
+        let path = cover.to_path_buf();
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mkv")
+            .cover(Some(cover))
+            .background(Some(PathBuf::from("fallback.jpg")))
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert_eq!(args[11], path.to_string_lossy());
+        assert!(path.exists());
+        drop(job);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_job_fps_drives_background_and_waveform() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mkv")
+            .background(Some(PathBuf::from("cover.jpg")))
+            .fps(25)
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert_eq!(args[6..10], ["-loop", "1", "-framerate", "25"]);
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(filter.contains(":rate=25,"));
+        assert!(args.windows(2).any(|w| w == ["-r", "25"]));
+    }
+
+    #[test]
+    fn test_ffmpeg_args_single_frame() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("still.png")
+            .preview(Preview {
+                frame: Some(90.0),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert!(args.windows(2).any(|w| w == ["-ss", "90.000"]));
+        assert!(args.windows(2).any(|w| w == ["-frames:v", "1"]));
+        assert!(!args.contains(&"1:a".to_string()));
+        assert!(!args.contains(&"-c:v".to_string()));
+    }
+
+    #[test]
+    fn test_captions_follow_preview_start() {
+        let cues = vec![
+            Cue { start: 5.0, end: 8.0, text: "Antes".to_string(), words: Vec::new() },
+            Cue { start: 62.0, end: 64.0, text: "Después".to_string(), words: Vec::new() },
+        ];
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mkv")
+            .captions(cues)
+            .preview(Preview {
+                start: Some(60.0),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        let path = job.captions.as_deref().unwrap();
+        assert!(filter.ends_with(&format!("subtitles=filename={}[outv]", path.display())));
+        let script = std::fs::read_to_string(path).unwrap();
+        assert!(!script.contains("Antes"));
+        assert!(script.contains("Dialogue: 0,0:00:02.00,0:00:04.00,Captions,"));
+        assert!(script.ends_with("Después\n"));
+    }
+
+    #[test]
+    fn test_long_transcripts_fit_in_the_arguments() {
+        // Una hora de subtítulos de tres segundos
+        let cues = (0..1200)
+            .map(|i| Cue {
+                start: i as f64 * 3.0,
+                end: i as f64 * 3.0 + 2.5,
+                text: format!("Subtítulo número {i} de un episodio bastante largo"),
+                words: Vec::new(),
+            })
+            .collect();
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mkv")
+            .captions(cues)
+            .build()
+            .unwrap();
+        // Límite de Linux para un argumento (MAX_ARG_STRLEN)
+        assert!(job.ffmpeg_args().iter().all(|arg| arg.len() < 128 * 1024));
+        let script = std::fs::read_to_string(job.captions.as_deref().unwrap()).unwrap();
+        assert_eq!(script.matches("Dialogue: ").count(), 1200);
+
+        let path = job.captions.as_deref().unwrap().to_path_buf();
+        drop(job);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_text_windows_follow_preview_frame() {
+        let layer = |text: &str, start: Option<f64>, end: Option<f64>| TextSettings {
+            text: Some(text.to_string()),
+            font: "sans-serif".to_string(),
+            size: 40,
+            color: "white".to_string(),
+            x: "10".to_string(),
+            y: "10".to_string(),
+            start,
+            end,
+            ..Default::default()
+        };
+        let mut template = sample_template();
+        template.texts = vec![
+            layer("Intro", None, Some(10.0)),
+            layer("Tarde", Some(30.0), None),
+            layer("Ventana", Some(50.0), Some(70.0)),
+        ];
+        let job = RenderJob::builder(template)
+            .input("in.mp3")
+            .output("still.png")
+            .preview(Preview { frame: Some(60.0), ..Default::default() })
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(!filter.contains("Intro"));
+        assert!(filter.contains("drawtext=text='Tarde'"));
+        assert!(!filter.contains("gte(t,30)"));
+        assert!(filter.contains("y=10:enable='lte(t,10)'[outv]"));
+    }
+
+    #[test]
+    fn test_title_is_drawn_literally() {
+        use crate::escape::tests::{av_get_token, drawtext_expand};
+        let mut template = sample_template();
+        template.title = Some(TextSettings {
+            font: "sans-serif".to_string(),
+            size: 40,
+            color: "white".to_string(),
+            x: "10".to_string(),
+            y: "100".to_string(),
+            ..Default::default()
+        });
+        let job = RenderJob::builder(template)
+            .input("in.mp3")
+            .output("out.mkv")
+            .var("x", "1")
+            .title("Set {x} vs {{y}}")
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        let (text, _) = av_get_token(filter.split("drawtext=text=").nth(1).unwrap(), ":");
+        assert_eq!(drawtext_expand(&text).as_deref(), Some("Set {x} vs {{y}}"));
+    }
+
+    #[test]
+    fn test_animations_follow_preview_start() {
+        let mut template = sample_template();
+        template.title = Some(TextSettings {
+            text: Some("Título".to_string()),
+            font: "sans-serif".to_string(),
+            size: 40,
+            color: "white".to_string(),
+            x: "10".to_string(),
+            y: "100".to_string(),
+            end: Some(10.0),
+            animation: Some(AnimationSettings {
+                fade_in: Some(0.5),
+                fade_out: Some(1.0),
+                slide: Some(SlideSettings { from: Edge::Top, duration: 0.6 }),
+                typewriter: Some(2.0),
+            }),
+            ..Default::default()
+        });
+        let job = RenderJob::builder(template)
+            .input("in.mp3")
+            .output("out.mkv")
+            .preview(Preview { start: Some(5.0), duration: Some(5.0), draft: true, ..Default::default() })
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        // Los tiempos de la animación cuentan desde el inicio real de la capa
+        assert!(filter.contains("alpha='clip(min((t+5)/0.5,(5-t)/1),0,1)'"));
+        assert!(filter.contains("pow(1-clip((t+5)/0.6,0,1),3)'"));
+        // La máquina de escribir ya terminó: solo queda el texto completo
+        assert_eq!(filter.matches("drawtext=").count(), 1);
+        assert!(filter.contains("drawtext=text='Título'"));
+        assert!(filter.contains(":enable='lte(t,5)'"));
+    }
+
+    #[test]
+    fn test_ffmpeg_args_gif() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("teaser.gif")
+            .preview(Preview {
+                duration: Some(5.0),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        assert_eq!(job.format(), Some(OutputFormat::Gif));
+        let args = job.ffmpeg_args();
+        assert!(args.windows(2).any(|w| w == ["-map", "[gif]"]));
+        assert!(!args.contains(&"1:a".to_string()));
+        assert!(args.windows(2).any(|w| w == ["-f", "gif"]));
+    }
+
+    #[test]
+    fn test_ffmpeg_args_transparent() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("overlay.mov")
+            .background(Some(PathBuf::from("cover.jpg")))
+            .transparent(true)
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert_eq!(args[6..10], ["-f", "lavfi", "-i", "color=c=black@0.0:s=1280x720:r=30,format=rgba"]);
+        assert!(!args.contains(&"cover.jpg".to_string()));
+        assert!(args.windows(2).any(|w| w == ["-c:v", "prores_ks"]));
+        assert!(args.windows(2).any(|w| w == ["-pix_fmt", "yuva444p10le"]));
+        assert!(args.windows(2).any(|w| w == ["-map", "1:a"]));
+
+        let result = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("overlay.mp4")
+            .transparent(true)
+            .build();
+        assert!(matches!(result, Err(AudiowaveError::IncompatibleFormat(_))));
+    }
+
+    #[test]
+    fn test_ffmpeg_args_generated_background() {
+        let mut template = sample_template();
+        template.background.path = String::new();
+        template.background.generate = Some(GeneratedBackground::Linear {
+            colors: vec!["#1e1e2e".to_string(), "#89b4fa".to_string()],
+            angle: 0.0,
+        });
+        let job = RenderJob::builder(template.clone())
+            .input("in.mp3")
+            .output("out.mp4")
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert_eq!(
+            args[6..10],
+            [
+                "-f",
+                "lavfi",
+                "-i",
+                "gradients=s=1280x720:r=30:c0=#1e1e2e:c1=#89b4fa:nb_colors=2:type=linear:x0=0:y0=360:x1=1280:y1=360:speed=0"
+            ]
+        );
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        // Sin retoque explícito el fondo generado no se oscurece
+        assert!(filter.starts_with("[0:v]null[bg];"));
+        assert!(!filter.contains("eq="));
+
+        // Los valores explícitos del retoque sí se aplican
+        let graded_filter = |grading: Grading| {
+            let mut template = template.clone();
+            template.background.grading = grading;
+            let job = RenderJob::builder(template).input("in.mp3").output("out.mp4").build().unwrap();
+            let args = job.ffmpeg_args();
+            args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1].clone()
+        };
+        let dimmed = Grading { brightness: Some(-0.2), ..Default::default() };
+        let vignette = Grading { vignette: Some(0.4), ..Default::default() };
+        assert!(graded_filter(dimmed).starts_with("[0:v]eq=brightness=-0.2[bg];"));
+        assert!(graded_filter(vignette).starts_with("[0:v]vignette=angle=0.6283[bg];"));
+
+        // La carátula tiene prioridad sobre el fondo generado
+        let job = RenderJob::builder(template)
+            .input("in.mp3")
+            .output("out.mp4")
+            .background(Some(PathBuf::from("cover.jpg")))
+            .build()
+            .unwrap();
+        assert_eq!(job.ffmpeg_args()[6..12], ["-loop", "1", "-framerate", "30", "-i", "cover.jpg"]);
+    }
+
+    #[test]
+    fn test_ffmpeg_args_video_background() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mp4")
+            .background(Some(PathBuf::from("loop.webm")))
+            .build()
+            .unwrap();
+        let args = job.ffmpeg_args();
+        assert_eq!(args[6..11], ["-stream_loop", "-1", "-an", "-i", "loop.webm"]);
+        let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(filter.starts_with("[0:v]fps=30,scale=1280:720,eq="));
+        assert!(args.contains(&"-shortest".to_string()));
+        assert!(!args.contains(&"0:a".to_string()));
+    }
+
+    #[test]
+    fn test_png_sequence_output() {
+        let job = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("frames.png")
+            .format(OutputFormat::Png)
+            .transparent(true)
+            .preview(Preview {
+                duration: Some(2.0),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        assert_eq!(job.output(), Path::new("frames_%05d.png"));
+        let args = job.ffmpeg_args();
+        assert!(args.windows(2).any(|w| w == ["-f", "image2"]));
+        assert!(args.windows(2).any(|w| w == ["-pix_fmt", "rgba"]));
+        assert!(!args.contains(&"1:a".to_string()));
+    }
+
+    #[test]
+    fn test_build_rejects_incompatible_format() {
+        let result = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mp4")
+            .format(OutputFormat::Webm)
+            .build();
+        assert!(matches!(result, Err(AudiowaveError::IncompatibleFormat(_))));
+
+        let mut template = sample_template();
+        template.encoding.video_codec = Some("libx264".to_string());
+        let result = RenderJob::builder(template).input("in.mp3").output("out.webm").build();
+        assert!(matches!(result, Err(AudiowaveError::IncompatibleFormat(_))));
+
+        let result = RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("teaser.webp")
+            .build();
+        assert!(matches!(result, Err(AudiowaveError::IncompatibleFormat(_))));
+    }
+
+    #[test]
+    fn test_audio_codec_is_ignored_without_audio() {
+        let mut template = sample_template();
+        template.encoding.audio_codec = Some("aac".to_string());
+        let job = RenderJob::builder(template)
+            .input("in.mp3")
+            .output("teaser.gif")
+            .preview(Preview { duration: Some(5.0), ..Default::default() })
+            .build()
+            .unwrap();
+        assert!(!job.ffmpeg_args().contains(&"aac".to_string()));
+    }
+
+    /// Backend falso: guarda los argumentos recibidos y reproduce una salida enlatada.
+    #[derive(Default)]
+    struct FakeBackend {
+        calls: Mutex<Vec<Vec<String>>>,
+        stderr: Vec<String>,
+        status: Option<ExitStatus>,
+    }
+
+    impl FakeBackend {
+        fn new(stderr: &[&str], code: i32) -> Self {
+            Self {
+                calls: Mutex::new(Vec::new()),
+                stderr: stderr.iter().map(|l| l.to_string()).collect(),
+                status: Some(ExitStatus {
+                    success: code == 0,
+                    code: Some(code),
+                }),
+            }
+        }
+    }
+
+    impl MediaBackend for FakeBackend {
+        async fn run(
+            &self,
+            args: Vec<String>,
+            lines: mpsc::UnboundedSender<String>,
+        ) -> Result<ExitStatus> {
+            self.calls.lock().unwrap().push(args);
+            for line in &self.stderr {
+                lines.send(line.clone()).unwrap();
+            }
+            self.status
+                .ok_or_else(|| AudiowaveError::FfmpegSpawn(std::io::Error::other("sin ffmpeg")))
+        }
+    }
+
+    fn sample_job() -> RenderJob {
+        RenderJob::builder(sample_template())
+            .input("in.mp3")
+            .output("out.mkv")
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_render_reports_progress() {
+        let backend = FakeBackend::new(
+            &[
+                "Input #0, png_pipe, from 'background.png':",
+                "  Duration: 00:00:00.04, start: 0.000000, bitrate: N/A",
+                "Input #1, mp3, from 'in.mp3':",
+                "  Duration: 00:01:40.00, start: 0.000000, bitrate: 128 kb/s",
+                "out_time_us=25000000",
+                "speed=5x",
+                "progress=continue",
+                "out_time_us=100000000",
+                "speed=5x",
+                "progress=end",
+            ],
+            0,
+        );
+        let renderer = Renderer::with_backend(backend);
+        let job = sample_job();
+        let expected_args = job.ffmpeg_args();
+
+        let mut handle = renderer.render(job);
+        let mut events = Vec::new();
+        while let Some(event) = handle.next_event().await {
+            events.push(event);
+        }
+        assert_eq!(handle.await.unwrap(), PathBuf::from("out.mkv"));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], RenderEvent::Duration { seconds: 100.0 });
+        match &events[1] {
+            RenderEvent::Progress(update) => {
+                assert_eq!(update.percent, 25.0);
+                assert_eq!(update.eta, Some(15.0));
+            }
+            other => panic!("evento inesperado: {other:?}"),
+        }
+        match &events[2] {
+            RenderEvent::Progress(update) => assert!(update.finished),
+            other => panic!("evento inesperado: {other:?}"),
+        }
+        assert_eq!(*renderer.backend.calls.lock().unwrap(), vec![expected_args]);
+    }
+
+    #[tokio::test]
+    async fn test_render_failure_keeps_last_log_lines() {
+        let mut stderr: Vec<String> = (0..20).map(|i| format!("línea {i}")).collect();
+        stderr.insert(10, "progress=continue".to_string());
+        let stderr: Vec<&str> = stderr.iter().map(String::as_str).collect();
+        let renderer = Renderer::with_backend(FakeBackend::new(&stderr, 1));
+
+        match renderer.render(sample_job()).await {
+            Err(AudiowaveError::FfmpegFailed { code, log }) => {
+                assert_eq!(code, Some(1));
+                assert_eq!(log.len(), ERROR_LOG_LINES);
+                assert_eq!(log.first().unwrap(), "línea 5");
+                assert_eq!(log.last().unwrap(), "línea 19");
+            }
+            other => panic!("resultado inesperado: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_spawn_failure() {
+        let renderer = Renderer::with_backend(FakeBackend::default());
+        let result = renderer.render(sample_job()).await;
+        assert!(matches!(result, Err(AudiowaveError::FfmpegSpawn(_))));
+    }
+
+    struct PanicBackend;
+
+    impl MediaBackend for PanicBackend {
+        async fn run(&self, _: Vec<String>, _: mpsc::UnboundedSender<String>) -> Result<ExitStatus> {
+            panic!("fallo interno del backend")
+        }
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "fallo interno del backend")]
+    async fn test_render_task_panic_is_propagated() {
+        let _ = Renderer::with_backend(PanicBackend).render(sample_job()).await;
+    }
+
+    /// Un FFmpeg falso que apunta su PID y se queda esperando.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_dropping_the_handle_kills_ffmpeg() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let program = dir.path().join("ffmpeg");
+        std::fs::write(&program, format!("#!/bin/sh\necho $$ > '{}'\nexec sleep 30\n", pid_file.display())).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let renderer = Renderer::with_backend(FfmpegBackend::new(program.to_string_lossy()));
+        let handle = renderer.render(sample_job());
+        let mut pid = None;
+        for _ in 0..100 {
+            pid = std::fs::read_to_string(&pid_file).ok().filter(|p| p.ends_with('\n'));
+            if pid.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let stat = format!("/proc/{}/stat", pid.expect("FFmpeg falso arrancado").trim());
+        // Un proceso terminado desaparece o queda como zombi (estado Z)
+        let running = || std::fs::read_to_string(&stat).is_ok_and(|s| !s.contains(") Z "));
+        assert!(running());
+
+        drop(handle);
+        for _ in 0..100 {
+            if !running() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("FFmpeg sigue en marcha tras soltar el renderizado");
+    }
+
+    #[test]
+    fn test_output_name_from_title() {
+        assert_eq!(
+            output_name_from_title("¿Qué hay de nuevo en Rust?", "mkv"),
+            "qué_hay_de_nuevo_en_rust.mkv"
+        );
+    }
+}
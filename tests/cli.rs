@@ -71,7 +71,8 @@ fn test_cli_basic_generation() -> Result<(), Box<dyn std::error::Error>> {
 
 
     let mut cmd = Command::new(cargo::cargo_bin!("audiowave"));
-    cmd.arg("-i")
+    cmd.arg("render")
+        .arg("-i")
         .arg(&audio_path)
         .arg("-o")
         .arg(&output_path)
@@ -85,3 +86,68 @@ fn test_cli_basic_generation() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_cli_styles_lists_every_style() {
+    let mut cmd = Command::new(cargo::cargo_bin!("audiowave"));
+    cmd.arg("styles");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("classic_line"))
+        .stdout(predicate::str::contains("circular_nebula"))
+        .stdout(predicate::str::contains("#00e5ff"));
+}
+
+#[test]
+fn test_cli_init_templates_and_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempdir()?;
+    let config_path = dir.path().join("config.yml");
+
+    Command::new(cargo::cargo_bin!("audiowave"))
+        .arg("init")
+        .arg(&config_path)
+        .assert()
+        .success();
+    assert!(config_path.exists());
+
+    // Sin --force no se sobreescribe
+    Command::new(cargo::cargo_bin!("audiowave"))
+        .arg("init")
+        .arg(&config_path)
+        .assert()
+        .failure();
+
+    Command::new(cargo::cargo_bin!("audiowave"))
+        .arg("templates")
+        .arg("-c")
+        .arg(&config_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("default  1920x1080  smooth_line"));
+
+    Command::new(cargo::cargo_bin!("audiowave"))
+        .arg("validate")
+        .arg("-c")
+        .arg(&config_path)
+        .arg("-t")
+        .arg("missing")
+        .assert()
+        .failure()
+        .code(5);
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_render_missing_config_exit_code() {
+    Command::new(cargo::cargo_bin!("audiowave"))
+        .arg("render")
+        .arg("-i")
+        .arg("input.mp3")
+        .arg("-c")
+        .arg("non_existent_config.yml")
+        .assert()
+        .failure()
+        .code(3);
+}